@@ -0,0 +1,13 @@
+import { NextResponse } from "next/server";
+import { forward, isGroupId } from "@/lib/scheduler";
+
+export async function POST(
+  req: Request,
+  { params }: { params: Promise<{ groupId: string }> }
+) {
+  const { groupId } = await params;
+  if (!isGroupId(groupId)) {
+    return NextResponse.json({ error: "invalid group id" }, { status: 400 });
+  }
+  return forward(`/execute/cron_job/${groupId}`);
+}
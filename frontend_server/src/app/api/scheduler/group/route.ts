@@ -0,0 +1,9 @@
+import { forward } from "@/lib/scheduler";
+
+export async function POST(req: Request) {
+  return forward("/group", {
+    method: "POST",
+    headers: { "Content-Type": "application/json" },
+    body: await req.text(),
+  });
+}
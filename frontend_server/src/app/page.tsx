@@ -40,43 +40,37 @@ export default function HomePage() {
   // Fetch jobs for selected group (always get canonical children/dependencies from backend)
   useEffect(() => {
     if (selectedGroup) {
-      const fetchJobs = () => {
-        fetch(`${process.env.NEXT_PUBLIC_API_URL}/cron_jobs/${selectedGroup.group_id}`)
-          .then((r) => r.json())
-          .then((data) => setJobs(data));
-      };
-      fetchJobs();
-      // Poll every 2 seconds for live updates
-      const interval = setInterval(fetchJobs, 2000);
-      return () => clearInterval(interval);
+      fetch(`${process.env.NEXT_PUBLIC_API_URL}/cron_jobs/${selectedGroup.group_id}`)
+        .then((r) => r.json())
+        .then((data) => setJobs(data));
     } else {
       setJobs([]);
     }
   }, [selectedGroup]);
 
-  // Poll job statuses every 2 seconds
+  // Follow job statuses live: a snapshot on connect, then one event per change
   useEffect(() => {
-    let interval: NodeJS.Timeout;
-    if (selectedGroup && jobs.length > 0) {
-      const fetchStatuses = () => {
-        jobs.forEach((job) => {
-          fetch(`${process.env.NEXT_PUBLIC_API_URL}/cron_job_status/${selectedGroup.group_id}/${job.cron_job_id}`)
-            .then((r) => r.json())
-            .then((status) => {
-              setJobStatuses((prev) => ({ ...prev, [job.cron_job_id]: status }));
-            });
-        });
-      };
-      fetchStatuses();
-      interval = setInterval(fetchStatuses, 2000);
-    } else {
-      setJobStatuses({});
-    }
-    return () => clearInterval(interval);
-  }, [selectedGroup, jobs]);
+    setJobStatuses({});
+    if (!selectedGroup) return;
+    const source = new EventSource(`${process.env.NEXT_PUBLIC_API_URL}/events/${selectedGroup.group_id}`);
+    source.addEventListener("snapshot", (e) => {
+      const snapshot = JSON.parse((e as MessageEvent).data);
+      const statuses: Record<string, JobStatus> = {};
+      for (const job of snapshot.jobs) {
+        if (job.status) statuses[job.job_id] = { ...job, group_id: snapshot.group_id };
+      }
+      setJobStatuses(statuses);
+    });
+    source.addEventListener("job_status", (e) => {
+      const status: JobStatus = JSON.parse((e as MessageEvent).data);
+      setJobStatuses((prev) => ({ ...prev, [status.job_id]: status }));
+    });
+    return () => source.close();
+  }, [selectedGroup]);
 
   const handleCreateGroup = async (groupName: string) => {
-    const res = await fetch(`${process.env.NEXT_PUBLIC_API_URL}/group`, {
+    // Actions that need the API key go through the dashboard's own server routes
+    const res = await fetch("/api/scheduler/group", {
       method: "POST",
       headers: { "Content-Type": "application/json" },
       body: JSON.stringify({ group_name: groupName }),
@@ -91,7 +85,7 @@ export default function HomePage() {
   };
 
   const handleCreateJob = async (data: any) => {
-    const res = await fetch(`${process.env.NEXT_PUBLIC_API_URL}/cron_job/${data.groupId}`, {
+    const res = await fetch(`/api/scheduler/cron_job/${data.groupId}`, {
       method: "POST",
       headers: { "Content-Type": "application/json" },
       body: JSON.stringify({
@@ -111,7 +105,7 @@ export default function HomePage() {
   const handleExecuteJobs = async () => {
     if (!selectedGroup) return;
     setExecuting(true);
-    await fetch(`${process.env.NEXT_PUBLIC_API_URL}/execute/cron_job/${selectedGroup.group_id}`);
+    await fetch(`/api/scheduler/execute/${selectedGroup.group_id}`, { method: "POST" });
     setExecuting(false);
   };
 
@@ -0,0 +1,22 @@
+// Server-side only: SCHEDULER_API_KEY must never be sent to the browser
+const API_URL = process.env.API_URL ?? process.env.NEXT_PUBLIC_API_URL;
+
+const UUID = /^[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}$/i;
+
+/** Whether a route parameter is a group id, so it cannot rewrite the forwarded path. */
+export function isGroupId(value: string): boolean {
+  return UUID.test(value);
+}
+
+/** Forward a dashboard action to the scheduler API with the dashboard's API key. */
+export async function forward(path: string, init: RequestInit = {}): Promise<Response> {
+  const res = await fetch(`${API_URL}${path}`, {
+    ...init,
+    headers: { ...init.headers, Authorization: `Bearer ${process.env.SCHEDULER_API_KEY}` },
+    cache: "no-store",
+  });
+  return new Response(res.body, {
+    status: res.status,
+    headers: { "Content-Type": res.headers.get("Content-Type") ?? "text/plain" },
+  });
+}
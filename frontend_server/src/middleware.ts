@@ -0,0 +1,70 @@
+import { NextRequest, NextResponse } from "next/server";
+
+// The /api/scheduler/* routes act with SCHEDULER_API_KEY, so the whole dashboard
+// sits behind HTTP Basic auth (DASHBOARD_USER / DASHBOARD_PASSWORD)
+const USER = process.env.DASHBOARD_USER ?? "admin";
+const PASSWORD = process.env.DASHBOARD_PASSWORD;
+
+/** Compare two strings in time independent of where they differ. */
+function safeEqual(a: string, b: string): boolean {
+  let diff = a.length ^ b.length;
+  for (let i = 0; i < Math.max(a.length, b.length); i++) {
+    diff |= (a.charCodeAt(i) || 0) ^ (b.charCodeAt(i) || 0);
+  }
+  return diff === 0;
+}
+
+function authorized(req: NextRequest, password: string): boolean {
+  const header = req.headers.get("authorization") ?? "";
+  if (!header.startsWith("Basic ")) {
+    return false;
+  }
+  let decoded: string;
+  try {
+    decoded = atob(header.slice("Basic ".length));
+  } catch {
+    return false;
+  }
+  const sep = decoded.indexOf(":");
+  if (sep < 0) {
+    return false;
+  }
+  // Evaluate both so a wrong user takes as long as a wrong password
+  const userOk = safeEqual(decoded.slice(0, sep), USER);
+  const passwordOk = safeEqual(decoded.slice(sep + 1), password);
+  return userOk && passwordOk;
+}
+
+/** Browsers resend Basic credentials cross-site, so actions must come from this origin. */
+function sameOrigin(req: NextRequest): boolean {
+  const origin = req.headers.get("origin");
+  if (!origin) {
+    return false;
+  }
+  try {
+    return new URL(origin).host === req.headers.get("host");
+  } catch {
+    return false;
+  }
+}
+
+export function middleware(req: NextRequest) {
+  if (!PASSWORD) {
+    return new NextResponse("DASHBOARD_PASSWORD is not set", { status: 503 });
+  }
+  if (!authorized(req, PASSWORD)) {
+    return new NextResponse("authentication required", {
+      status: 401,
+      headers: { "WWW-Authenticate": 'Basic realm="scheduler dashboard", charset="UTF-8"' },
+    });
+  }
+  if (req.method !== "GET" && req.method !== "HEAD" && !sameOrigin(req)) {
+    return new NextResponse("cross-origin request refused", { status: 403 });
+  }
+  return NextResponse.next();
+}
+
+export const config = {
+  // Everything but Next's static assets
+  matcher: ["/((?!_next/static|_next/image|favicon.ico).*)"],
+};
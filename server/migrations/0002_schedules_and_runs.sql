@@ -0,0 +1,27 @@
+-- Cron schedules and the history of group runs and job attempts
+ALTER TABLE cron_jobs ADD COLUMN IF NOT EXISTS schedule TEXT; -- cron expression (5 or 6 fields); NULL means manual execution only
+
+-- One row per execution of a group
+CREATE TABLE IF NOT EXISTS group_runs (
+    run_id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    group_id UUID NOT NULL REFERENCES job_groups(group_id) ON DELETE CASCADE,
+    status TEXT NOT NULL, -- 'running', 'completed', 'failed'
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+-- One row per job attempt within a group run
+CREATE TABLE IF NOT EXISTS job_runs (
+    job_run_id SERIAL PRIMARY KEY,
+    run_id UUID NOT NULL REFERENCES group_runs(run_id) ON DELETE CASCADE,
+    cron_job_id INT NOT NULL REFERENCES cron_jobs(cron_job_id) ON DELETE CASCADE,
+    attempt INT NOT NULL DEFAULT 1, -- 1 for the first try, incremented per retry
+    status TEXT NOT NULL, -- 'running', 'completed', 'failed', 'timed_out'
+    exit_code INT,
+    log_path TEXT, -- captured build and run output for this attempt
+    started_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS idx_group_runs_group_id ON group_runs(group_id);
+CREATE INDEX IF NOT EXISTS idx_job_runs_run_id ON job_runs(run_id);
@@ -0,0 +1,5 @@
+-- Per-job retry policy and execution time limit
+ALTER TABLE cron_jobs ADD COLUMN IF NOT EXISTS max_retries INT NOT NULL DEFAULT 0; -- extra attempts after the first failure
+ALTER TABLE cron_jobs ADD COLUMN IF NOT EXISTS retry_delay_seconds INT NOT NULL DEFAULT 0; -- delay before the first retry
+ALTER TABLE cron_jobs ADD COLUMN IF NOT EXISTS backoff_multiplier DOUBLE PRECISION NOT NULL DEFAULT 2.0; -- delay growth factor per retry
+ALTER TABLE cron_jobs ADD COLUMN IF NOT EXISTS timeout_seconds INT; -- kill the container after this many seconds; NULL means no limit
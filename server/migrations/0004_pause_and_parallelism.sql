@@ -0,0 +1,5 @@
+-- Pausing groups and jobs, and per-group concurrency limits
+ALTER TABLE job_groups ADD COLUMN IF NOT EXISTS created_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+ALTER TABLE job_groups ADD COLUMN IF NOT EXISTS paused BOOLEAN NOT NULL DEFAULT FALSE; -- paused groups are neither scheduled nor executed
+ALTER TABLE job_groups ADD COLUMN IF NOT EXISTS max_parallelism INT; -- most jobs of the group running at once; NULL means only the global limit applies
+ALTER TABLE cron_jobs ADD COLUMN IF NOT EXISTS paused BOOLEAN NOT NULL DEFAULT FALSE; -- paused jobs are skipped, along with their dependents
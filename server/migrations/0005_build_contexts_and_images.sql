@@ -0,0 +1,3 @@
+-- Build contexts with a custom Dockerfile location, and the image each attempt ran
+ALTER TABLE cron_jobs ADD COLUMN IF NOT EXISTS dockerfile_path TEXT; -- Dockerfile location inside a build context; NULL means 'Dockerfile'
+ALTER TABLE job_runs ADD COLUMN IF NOT EXISTS image_digest TEXT; -- sha256 of the build input the attempt's image was built from
@@ -0,0 +1,9 @@
+-- API keys for mutating and execute endpoints; only a hash of each key is stored
+CREATE TABLE IF NOT EXISTS api_keys (
+    key_id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL, -- who or what the key was issued to
+    key_hash TEXT NOT NULL UNIQUE, -- hex sha256 of the key
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ -- revoked keys are kept for auditing but no longer accepted
+);
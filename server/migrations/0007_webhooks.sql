@@ -0,0 +1,24 @@
+-- Per-group webhooks and a record of every delivery
+CREATE TABLE IF NOT EXISTS group_webhooks (
+    webhook_id SERIAL PRIMARY KEY,
+    group_id UUID NOT NULL REFERENCES job_groups(group_id) ON DELETE CASCADE,
+    url TEXT NOT NULL, -- receives a JSON POST per event
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    delivery_id SERIAL PRIMARY KEY,
+    webhook_id INT NOT NULL REFERENCES group_webhooks(webhook_id) ON DELETE CASCADE,
+    run_id UUID NOT NULL,
+    event TEXT NOT NULL, -- 'job_failed', 'job_timed_out', 'run_completed'
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL, -- 'pending', 'delivered', 'failed'
+    attempts INT NOT NULL DEFAULT 0,
+    response_status INT, -- HTTP status of the latest attempt, if it got a response
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_group_webhooks_group_id ON group_webhooks(group_id);
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook_id ON webhook_deliveries(webhook_id);
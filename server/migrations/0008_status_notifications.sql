@@ -0,0 +1,34 @@
+-- Publish every job and group run status change on the 'group_events' channel,
+-- which the server relays to live status streams
+CREATE OR REPLACE FUNCTION notify_job_status() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('group_events', json_build_object(
+        'kind', 'job_status',
+        'group_id', NEW.group_id,
+        'job_id', NEW.cron_job_id,
+        'status', NEW.status,
+        'updated_at', NEW.updated_at
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION notify_group_run() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('group_events', json_build_object(
+        'kind', 'run_status',
+        'group_id', NEW.group_id,
+        'run_id', NEW.run_id,
+        'status', NEW.status,
+        'started_at', NEW.started_at,
+        'finished_at', NEW.finished_at
+    )::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER job_status_notify AFTER INSERT OR UPDATE ON job_status
+    FOR EACH ROW EXECUTE FUNCTION notify_job_status();
+
+CREATE OR REPLACE TRIGGER group_runs_notify AFTER INSERT OR UPDATE OF status ON group_runs
+    FOR EACH ROW EXECUTE FUNCTION notify_group_run();
@@ -0,0 +1,141 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+
+/// Prefix of every API key, so a leaked key is easy to recognize.
+const KEY_PREFIX: &str = "jsk_";
+
+#[derive(Serialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub key_id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// A new random key: the prefix followed by 32 random bytes in hex.
+fn generate_key() -> String {
+    let bytes: [u8; 32] = rand::random();
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}{}", KEY_PREFIX, hex)
+}
+
+fn hash_key(key: &str) -> String {
+    format!("{:x}", Sha256::digest(key.as_bytes()))
+}
+
+/// Store a new key for `name`, returning its id and the plaintext key.
+pub async fn insert_api_key(pool: &PgPool, name: &str) -> Result<(i32, String), String> {
+    if name.trim().is_empty() {
+        return Err("name must not be empty".to_string());
+    }
+    let key = generate_key();
+    let key_id = sqlx::query_scalar!(
+        "INSERT INTO api_keys (name, key_hash) VALUES ($1, $2) RETURNING key_id",
+        name,
+        hash_key(&key)
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok((key_id, key))
+}
+
+pub async fn list_api_keys(pool: &PgPool) -> Result<Vec<ApiKey>, String> {
+    sqlx::query_as!(
+        ApiKey,
+        "SELECT key_id, name, created_at, last_used_at, revoked_at FROM api_keys ORDER BY key_id"
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Revoke a key; returns false if there is no active key with that id.
+pub async fn revoke_key(pool: &PgPool, key_id: i32) -> Result<bool, String> {
+    let result = sqlx::query!(
+        "UPDATE api_keys SET revoked_at = NOW() WHERE key_id = $1 AND revoked_at IS NULL",
+        key_id
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Middleware rejecting requests without a valid `Authorization: Bearer <key>` header.
+pub async fn require_api_key(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let key = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    let Some(key) = key else {
+        return unauthorized("missing bearer token");
+    };
+
+    // Checks the key and records its use in one statement
+    let key_id = sqlx::query_scalar!(
+        r#"
+        UPDATE api_keys SET last_used_at = NOW()
+        WHERE key_hash = $1 AND revoked_at IS NULL
+        RETURNING key_id
+        "#,
+        hash_key(key)
+    )
+    .fetch_optional(&state.pool)
+    .await;
+
+    match key_id {
+        Ok(Some(_)) => next.run(request).await,
+        Ok(None) => unauthorized("invalid or revoked api key"),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+fn unauthorized(reason: &'static str) -> Response {
+    (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")], reason).into_response()
+}
+
+/// Run `server api-key <create NAME | list | revoke KEY_ID>`, printing the result.
+///
+/// Keys are only managed from the command line, so a leaked key cannot mint more.
+pub async fn run_api_key_command(pool: &PgPool, args: &[String]) -> Result<(), String> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["create", name] => {
+            let (key_id, key) = insert_api_key(pool, name).await?;
+            println!("created api key {} for '{}' (it will not be shown again):", key_id, name);
+            println!("{}", key);
+        }
+        ["list"] => {
+            for key in list_api_keys(pool).await? {
+                let state = match key.revoked_at {
+                    Some(at) => format!("revoked {}", at.to_rfc3339()),
+                    None => "active".to_string(),
+                };
+                let last_used = key.last_used_at.map_or("never".to_string(), |at| at.to_rfc3339());
+                println!("{}\t{}\t{}\tlast used {}", key.key_id, key.name, state, last_used);
+            }
+        }
+        ["revoke", key_id] => {
+            let key_id: i32 = key_id.parse().map_err(|_| format!("invalid key id '{}'", key_id))?;
+            if !revoke_key(pool, key_id).await? {
+                return Err(format!("active api key {} not found", key_id));
+            }
+            println!("revoked api key {}", key_id);
+        }
+        _ => return Err("usage: server api-key <create NAME | list | revoke KEY_ID>".to_string()),
+    }
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::scheduler::slots::DEFAULT_MAX_RUNNING_JOBS;
+
+/// Config file read when `CONFIG_FILE` is not set, if it exists.
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Server settings, read from a TOML file and then overridden by environment variables.
+///
+/// Every field has a default except the database URL. See `config.example.toml`
+/// for the file layout; the environment variable for each field is noted next to it.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub database: DatabaseConfig,
+    pub executor: ExecutorConfig,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_addr: String,         // BIND_ADDR
+    pub cors_origins: Vec<String>, // CORS_ORIGINS, comma separated; empty allows any origin
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,          // DATABASE_URL
+    pub max_connections: u32,         // DB_MAX_CONNECTIONS
+    pub min_connections: u32,         // DB_MIN_CONNECTIONS
+    pub acquire_timeout_seconds: u64, // DB_ACQUIRE_TIMEOUT_SECONDS
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExecutorConfig {
+    pub work_dir: PathBuf,                        // WORK_DIR; job logs and build contexts live here
+    pub docker_host: String,                      // DOCKER_HOST; unix://, tcp:// or http(s):// address
+    pub max_running_jobs: usize,                  // MAX_RUNNING_JOBS
+    pub download_timeout_seconds: u64,            // DOWNLOAD_TIMEOUT_SECONDS; fetching build input
+    pub default_job_timeout_seconds: Option<u64>, // DEFAULT_JOB_TIMEOUT_SECONDS; for jobs without timeout_seconds
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: "0.0.0.0:5000".to_string(),
+            cors_origins: Vec::new(),
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout_seconds: 30,
+        }
+    }
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        Self {
+            work_dir: PathBuf::from("/tmp"),
+            docker_host: "unix:///var/run/docker.sock".to_string(),
+            max_running_jobs: DEFAULT_MAX_RUNNING_JOBS,
+            download_timeout_seconds: 300,
+            default_job_timeout_seconds: None,
+        }
+    }
+}
+
+impl Config {
+    /// Load the config file named by `CONFIG_FILE` (or `config.toml` if present),
+    /// apply environment overrides and validate the result.
+    pub fn load() -> Result<Self, String> {
+        let mut config = match std::env::var("CONFIG_FILE") {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?,
+            Err(_) => Self::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("reading {}: {}", path.display(), e))?;
+        toml::from_str(&text).map_err(|e| format!("parsing {}: {}", path.display(), e))
+    }
+
+    fn apply_env(&mut self) -> Result<(), String> {
+        env_override("BIND_ADDR", &mut self.server.bind_addr)?;
+        if let Ok(origins) = std::env::var("CORS_ORIGINS") {
+            self.server.cors_origins = origins
+                .split(',')
+                .map(str::trim)
+                .filter(|o| !o.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        if let Ok(url) = std::env::var("DATABASE_URL") {
+            self.database.url = Some(url);
+        }
+        env_override("DB_MAX_CONNECTIONS", &mut self.database.max_connections)?;
+        env_override("DB_MIN_CONNECTIONS", &mut self.database.min_connections)?;
+        env_override("DB_ACQUIRE_TIMEOUT_SECONDS", &mut self.database.acquire_timeout_seconds)?;
+        env_override("WORK_DIR", &mut self.executor.work_dir)?;
+        env_override("DOCKER_HOST", &mut self.executor.docker_host)?;
+        env_override("MAX_RUNNING_JOBS", &mut self.executor.max_running_jobs)?;
+        env_override("DOWNLOAD_TIMEOUT_SECONDS", &mut self.executor.download_timeout_seconds)?;
+        if let Ok(value) = std::env::var("DEFAULT_JOB_TIMEOUT_SECONDS") {
+            self.executor.default_job_timeout_seconds = Some(parse_env("DEFAULT_JOB_TIMEOUT_SECONDS", &value)?);
+        }
+        Ok(())
+    }
+
+    /// Reject settings the server could not start or run jobs with.
+    fn validate(&self) -> Result<(), String> {
+        self.bind_addr()?;
+        if self.database.url.as_deref().is_none_or(str::is_empty) {
+            return Err("database url is required (set DATABASE_URL or database.url)".to_string());
+        }
+        if self.database.max_connections == 0 {
+            return Err("database.max_connections must be positive".to_string());
+        }
+        if self.database.min_connections > self.database.max_connections {
+            return Err("database.min_connections must not exceed database.max_connections".to_string());
+        }
+        if !self.executor.work_dir.is_absolute() {
+            return Err(format!(
+                "executor.work_dir must be an absolute path, got '{}'",
+                self.executor.work_dir.display()
+            ));
+        }
+        docker_endpoint(&self.executor.docker_host)?;
+        if self.executor.max_running_jobs == 0 {
+            return Err("executor.max_running_jobs must be positive".to_string());
+        }
+        if self.executor.download_timeout_seconds == 0 {
+            return Err("executor.download_timeout_seconds must be positive".to_string());
+        }
+        if self.executor.default_job_timeout_seconds == Some(0) {
+            return Err("executor.default_job_timeout_seconds must be positive".to_string());
+        }
+        for origin in &self.server.cors_origins {
+            if origin != "*" && axum::http::HeaderValue::from_str(origin).is_err() {
+                return Err(format!("invalid CORS origin '{}'", origin));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn bind_addr(&self) -> Result<SocketAddr, String> {
+        self.server
+            .bind_addr
+            .parse()
+            .map_err(|e| format!("invalid server.bind_addr '{}': {}", self.server.bind_addr, e))
+    }
+
+    pub fn database_url(&self) -> &str {
+        self.database.url.as_deref().unwrap_or_default()
+    }
+
+    /// Directory under which job attempt logs are written.
+    pub fn log_dir(&self) -> PathBuf {
+        self.executor.work_dir.join("cron_job_logs")
+    }
+
+    /// Directory under which job attempts unpack their build contexts.
+    pub fn build_dir(&self) -> PathBuf {
+        self.executor.work_dir.join("cron_job_builds")
+    }
+
+    pub fn download_timeout(&self) -> Duration {
+        Duration::from_secs(self.executor.download_timeout_seconds)
+    }
+
+    /// Client for the configured Docker daemon.
+    pub fn docker(&self) -> shiplift::Docker {
+        match docker_endpoint(&self.executor.docker_host) {
+            Ok(DockerEndpoint::Unix(path)) => shiplift::Docker::unix(path),
+            Ok(DockerEndpoint::Http(uri)) => shiplift::Docker::host(uri),
+            Err(_) => shiplift::Docker::new(),
+        }
+    }
+}
+
+enum DockerEndpoint {
+    Unix(String),
+    Http(shiplift::Uri),
+}
+
+/// Parse `docker_host` the way the Docker CLI accepts `DOCKER_HOST`.
+fn docker_endpoint(host: &str) -> Result<DockerEndpoint, String> {
+    if let Some(path) = host.strip_prefix("unix://") {
+        return Ok(DockerEndpoint::Unix(path.to_string()));
+    }
+    let url = match host.strip_prefix("tcp://") {
+        Some(rest) => format!("http://{}", rest),
+        None => host.to_string(),
+    };
+    let uri: shiplift::Uri = url
+        .parse()
+        .map_err(|e| format!("invalid executor.docker_host '{}': {}", host, e))?;
+    if !matches!(uri.scheme_str(), Some("http" | "https")) || uri.host().is_none() {
+        return Err(format!(
+            "invalid executor.docker_host '{}': expected unix://, tcp://, http:// or https://",
+            host
+        ));
+    }
+    Ok(DockerEndpoint::Http(uri))
+}
+
+fn parse_env<T: FromStr>(name: &str, value: &str) -> Result<T, String>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| format!("invalid {} '{}': {}", name, value, e))
+}
+
+/// Replace `target` with the parsed value of environment variable `name`, if set.
+fn env_override<T: FromStr>(name: &str, target: &mut T) -> Result<(), String>
+where
+    T::Err: std::fmt::Display,
+{
+    if let Ok(value) = std::env::var(name) {
+        *target = parse_env(name, &value)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use sqlx::PgPool;
+use sqlx::migrate::Migrator;
+
+/// Schema migrations from `migrations/`, embedded in the binary.
+///
+/// Migrations only ever add to the schema; each one is idempotent so a database
+/// set up by hand with the same tables is adopted rather than rebuilt.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Apply every migration the database has not run yet, in order.
+pub async fn migrate(pool: &PgPool) -> Result<(), String> {
+    MIGRATOR
+        .run(pool)
+        .await
+        .map_err(|e| format!("applying migrations failed: {}", e))
+}
@@ -1 +1,6 @@
+pub mod auth;
+pub mod config;
+pub mod db;
+pub mod metrics;
 pub mod scheduler;
+pub mod state;
@@ -1,52 +1,209 @@
 use axum::{
     Router,
-    routing::{get, post},
+    http::header,
+    middleware,
+    routing::{delete, get, patch, post, put},
 };
-use tower_http::cors::{CorsLayer, Any};
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use server::scheduler::{
     create_job_group,
+    delete_job_group,
+    get_job_group,
+    pause_job_group,
+    resume_job_group,
+    update_job_group,
     cron::{
         add_cron_job,
+        delete_cron_job,
         execute_cron_jobs_for_group,
         get_cron_job_status,
+        get_group_status,
         get_groups,
         get_jobs_for_group,
+        pause_cron_job,
+        resume_cron_job,
+        update_cron_job,
     },
+    logs::get_cron_job_logs,
+    runs::{cancel_group_runs, get_group_run, get_group_runs},
+    definition::{create_group_from_definition, export_group_definition, import_group_definition},
+    events::{relay_status_events, stream_group_events},
+    graph::get_group_graph,
+    schedule::run_scheduler,
+    webhooks::{add_webhook, delete_webhook, get_webhook_deliveries, get_webhooks},
 };
-use sqlx::PgPool;
+use server::auth::{require_api_key, run_api_key_command};
+use server::config::Config;
+use server::db;
+use server::metrics::{get_metrics, track_http};
+use server::state::AppState;
+use sqlx::postgres::PgPoolOptions;
+use tracing_subscriber::EnvFilter;
+
+enum Command {
+    Serve,
+    Migrate,
+    ApiKey,
+}
 
 #[tokio::main]
 async fn main() {
     dotenvy::dotenv().ok();
-    let pool = PgPool::connect(&std::env::var("DATABASE_URL").unwrap())
+
+    // Log level comes from RUST_LOG (default `info`); LOG_FORMAT=json switches to JSON lines
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let logs = tracing_subscriber::fmt().with_env_filter(filter);
+    if std::env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json")) {
+        logs.json().init();
+    } else {
+        logs.init();
+    }
+
+    // `server` applies pending migrations and serves; `server migrate` only applies them;
+    // `server api-key ...` manages API keys
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match args.first().map(String::as_str) {
+        None => Command::Serve,
+        Some("migrate") => Command::Migrate,
+        Some("api-key") => Command::ApiKey,
+        Some(other) => {
+            tracing::error!("unknown command '{}' (expected none, 'migrate' or 'api-key')", other);
+            std::process::exit(2);
+        }
+    };
+
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            tracing::error!("invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let pool = match PgPoolOptions::new()
+        .max_connections(config.database.max_connections)
+        .min_connections(config.database.min_connections)
+        .acquire_timeout(std::time::Duration::from_secs(config.database.acquire_timeout_seconds))
+        .connect(config.database_url())
         .await
-        .unwrap();
+    {
+        Ok(pool) => pool,
+        Err(e) => {
+            tracing::error!("connecting to database failed: {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    // build our application with a single route
-    let app = Router::new()
-        .route("/", get(|| async { "Hello, World!" }))
+    if let Err(e) = db::migrate(&pool).await {
+        tracing::error!("{}", e);
+        std::process::exit(1);
+    }
+    match command {
+        Command::Serve => {}
+        Command::Migrate => {
+            tracing::info!("database is up to date");
+            return;
+        }
+        Command::ApiKey => {
+            if let Err(e) = run_api_key_command(&pool, &args[1..]).await {
+                tracing::error!("{}", e);
+                std::process::exit(1);
+            }
+            return;
+        }
+    }
+
+    // No origins configured, or "*", allows any origin
+    let origins = &config.server.cors_origins;
+    let allow_origin = if origins.is_empty() || origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        // Origins were checked by Config::validate
+        AllowOrigin::list(origins.iter().map(|o| o.parse().unwrap()))
+    };
+    let bind_addr = config.bind_addr().unwrap();
+    let state = AppState::new(pool, config);
+
+    // Background loop that triggers jobs on their cron schedule
+    tokio::spawn(run_scheduler(state.clone()));
+    // Background task feeding status changes from Postgres to event streams
+    tokio::spawn(relay_status_events(state.pool.clone(), state.events.clone()));
+
+    // Anything that changes state or runs containers needs an API key
+    let protected = Router::new()
         .route("/group", post(create_job_group))
-        .route("/groups", get(get_groups))
+        .route(
+            "/group/{group_id}",
+            patch(update_job_group).delete(delete_job_group),
+        )
+        .route("/group_definition", post(create_group_from_definition))
+        .route("/group/{group_id}/definition", put(import_group_definition))
+        .route("/group/{group_id}/pause", post(pause_job_group))
+        .route("/group/{group_id}/resume", post(resume_job_group))
         .route("/cron_job/{group_id}", post(add_cron_job))
-        .route("/cron_jobs/{group_id}", get(get_jobs_for_group))
+        .route(
+            "/cron_job/{group_id}/{job_id}",
+            patch(update_cron_job).delete(delete_cron_job),
+        )
+        .route("/cron_job/{group_id}/{job_id}/pause", post(pause_cron_job))
+        .route("/cron_job/{group_id}/{job_id}/resume", post(resume_cron_job))
         .route(
             "/execute/cron_job/{group_id}",
             get(execute_cron_jobs_for_group),
         )
+        .route("/cancel/{group_id}", post(cancel_group_runs))
+        .route("/webhook/{group_id}", post(add_webhook))
+        .route("/webhook/{group_id}/{webhook_id}", delete(delete_webhook))
+        // Webhook URLs often embed credentials, so reading them needs a key too
+        .route("/webhooks/{group_id}", get(get_webhooks))
+        .route("/webhook_deliveries/{group_id}", get(get_webhook_deliveries))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_api_key));
+
+    let app = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .route("/group/{group_id}", get(get_job_group))
+        .route("/group/{group_id}/graph", get(get_group_graph))
+        .route("/group/{group_id}/definition", get(export_group_definition))
+        .route("/groups", get(get_groups))
+        .route("/group_status/{group_id}", get(get_group_status))
+        .route("/cron_jobs/{group_id}", get(get_jobs_for_group))
         .route(
             "/cron_job_status/{group_id}/{job_id}",
             get(get_cron_job_status),
         )
-        .with_state(pool.clone())
+        .route(
+            "/cron_job_logs/{group_id}/{job_id}",
+            get(get_cron_job_logs),
+        )
+        .route("/events/{group_id}", get(stream_group_events))
+        .route("/runs/{group_id}", get(get_group_runs))
+        .route("/run/{run_id}", get(get_group_run))
+        .route("/metrics", get(get_metrics))
+        .merge(protected)
+        .route_layer(middleware::from_fn_with_state(state.clone(), track_http))
+        .with_state(state)
         .layer(
             CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods([axum::http::Method::GET, axum::http::Method::POST])
-                .allow_headers(Any)
+                .allow_origin(allow_origin)
+                .allow_methods([
+                    axum::http::Method::GET,
+                    axum::http::Method::POST,
+                    axum::http::Method::PATCH,
+                    axum::http::Method::PUT,
+                    axum::http::Method::DELETE,
+                ])
+                .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
         );
     // .route("/ad_hoc", post(add_ad_hoc_job));
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:5000").await.unwrap();
+    let listener = match tokio::net::TcpListener::bind(bind_addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("binding {} failed: {}", bind_addr, e);
+            std::process::exit(1);
+        }
+    };
+    tracing::info!("listening on {}", listener.local_addr().unwrap());
     axum::serve(listener, app).await.unwrap();
 }
 // 545c6238-da90-4ef8-8e3c-a7aab9f3c883
@@ -0,0 +1,125 @@
+use crate::state::AppState;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use std::time::Instant;
+
+/// Buckets for build and run durations, from a second up to an hour.
+const JOB_DURATION_BUCKETS: &[f64] = &[1.0, 5.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0];
+
+/// Prometheus metrics for the executor and the HTTP API, served at `/metrics`.
+///
+/// Job metrics are labeled by `group_id`; HTTP metrics by route template only,
+/// since request paths may name any group id, existing or not.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    pub job_attempts: IntCounterVec,       // group_id, status
+    pub build_duration: HistogramVec,      // group_id
+    pub run_duration: HistogramVec,        // group_id, status
+    pub queued_jobs: IntGaugeVec,          // group_id
+    pub running_containers: IntGaugeVec,   // group_id
+    pub group_runs: IntCounterVec,         // group_id, status
+    pub http_duration: HistogramVec,       // method, route, status
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let job_attempts = IntCounterVec::new(
+            Opts::new("scheduler_job_attempts_total", "Finished job attempts by outcome"),
+            &["group_id", "status"],
+        )
+        .unwrap();
+        let build_duration = HistogramVec::new(
+            HistogramOpts::new("scheduler_job_build_duration_seconds", "Time spent building job images")
+                .buckets(JOB_DURATION_BUCKETS.to_vec()),
+            &["group_id"],
+        )
+        .unwrap();
+        let run_duration = HistogramVec::new(
+            HistogramOpts::new("scheduler_job_run_duration_seconds", "Time job containers ran, by outcome")
+                .buckets(JOB_DURATION_BUCKETS.to_vec()),
+            &["group_id", "status"],
+        )
+        .unwrap();
+        let queued_jobs = IntGaugeVec::new(
+            Opts::new("scheduler_queued_jobs", "Jobs waiting for a free job slot"),
+            &["group_id"],
+        )
+        .unwrap();
+        let running_containers = IntGaugeVec::new(
+            Opts::new("scheduler_running_containers", "Job containers currently running"),
+            &["group_id"],
+        )
+        .unwrap();
+        let group_runs = IntCounterVec::new(
+            Opts::new("scheduler_group_runs_total", "Finished group runs by outcome"),
+            &["group_id", "status"],
+        )
+        .unwrap();
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency by route"),
+            &["method", "route", "status"],
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(job_attempts.clone())).unwrap();
+        registry.register(Box::new(build_duration.clone())).unwrap();
+        registry.register(Box::new(run_duration.clone())).unwrap();
+        registry.register(Box::new(queued_jobs.clone())).unwrap();
+        registry.register(Box::new(running_containers.clone())).unwrap();
+        registry.register(Box::new(group_runs.clone())).unwrap();
+        registry.register(Box::new(http_duration.clone())).unwrap();
+
+        Self {
+            registry,
+            job_attempts,
+            build_duration,
+            run_duration,
+            queued_jobs,
+            running_containers,
+            group_runs,
+            http_duration,
+        }
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handler to expose all metrics in the Prometheus text format.
+pub async fn get_metrics(State(state): State<AppState>) -> Result<Response, (StatusCode, String)> {
+    let mut body = Vec::new();
+    TextEncoder::new()
+        .encode(&state.metrics.registry.gather(), &mut body)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], body).into_response())
+}
+
+/// Middleware recording the latency of every request against its route template.
+pub async fn track_http(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|r| r.as_str().to_string())
+        .unwrap_or_default();
+    let response = next.run(request).await;
+    state
+        .metrics
+        .http_duration
+        .with_label_values(&[&method, &route, response.status().as_str()])
+        .observe(started.elapsed().as_secs_f64());
+    response
+}
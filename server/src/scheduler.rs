@@ -1,38 +1,65 @@
 use axum::{
-    extract::{Json, State},
+    extract::{Json, Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::state::AppState;
+
 pub mod cron;
+pub mod dag;
+pub mod definition;
+pub mod events;
+pub mod graph;
+pub mod logs;
+pub mod runs;
 pub mod s3;
+pub mod schedule;
+pub mod slots;
+pub mod webhooks;
 
 #[derive(Deserialize)]
 pub struct CreateGroupRequest {
     group_name: String,
+    max_parallelism: Option<i32>, // most jobs of the group running at once
 }
 
 #[derive(Serialize)]
 pub struct CreateGroupResponse {
     group_id: Uuid,
     group_name: String,
+    max_parallelism: Option<i32>,
+}
+
+/// Reject a `max_parallelism` that would never let a job start.
+pub(crate) fn validate_max_parallelism(max_parallelism: Option<i32>) -> Result<(), String> {
+    if max_parallelism.is_some_and(|n| n <= 0) {
+        return Err("max_parallelism must be positive".to_string());
+    }
+    Ok(())
 }
 
 pub async fn create_job_group(
     State(pool): State<PgPool>,
     Json(payload): Json<CreateGroupRequest>,
 ) -> impl IntoResponse {
+    if let Err(e) = validate_max_parallelism(payload.max_parallelism) {
+        return (StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({ "error": e }))).into_response();
+    }
+
     // Returning *both* group_id and group_name from SQL
     let result = sqlx::query!(
         r#"
-        INSERT INTO job_groups (group_name)
-        VALUES ($1)
-        RETURNING group_id, group_name
+        INSERT INTO job_groups (group_name, max_parallelism)
+        VALUES ($1, $2)
+        RETURNING group_id, group_name, max_parallelism
         "#,
-        payload.group_name
+        payload.group_name,
+        payload.max_parallelism
     )
     .fetch_one(&pool)
     .await;
@@ -42,11 +69,12 @@ pub async fn create_job_group(
             let response = CreateGroupResponse {
                 group_id: record.group_id,
                 group_name: record.group_name,
+                max_parallelism: record.max_parallelism,
             };
             (StatusCode::CREATED, Json(response)).into_response()
         }
         Err(e) => {
-            eprintln!("Error creating group: {:?}", e);
+            tracing::error!(error = %e, "failed to create group");
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({ "error": "Failed to create group" })),
@@ -56,5 +84,177 @@ pub async fn create_job_group(
     }
 }
 
+/// Summary of a group's most recent run.
+#[derive(Serialize)]
+pub struct LastRun {
+    run_id: Uuid,
+    status: String,
+    started_at: DateTime<Utc>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct GroupDetail {
+    group_id: Uuid,
+    group_name: String,
+    created_at: DateTime<Utc>,
+    paused: bool,
+    max_parallelism: Option<i32>,
+    job_count: i64,
+    last_run: Option<LastRun>,
+}
+
+/// Handler to describe a group: job count, creation time and last run.
+pub async fn get_job_group(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<GroupDetail>, (StatusCode, String)> {
+    let group = sqlx::query!(
+        r#"
+        SELECT g.group_id, g.group_name, g.created_at, g.paused, g.max_parallelism,
+               (SELECT COUNT(*) FROM cron_jobs j WHERE j.group_id = g.group_id) AS "job_count!"
+        FROM job_groups g
+        WHERE g.group_id = $1
+        "#,
+        group_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, format!("group {} not found", group_id)))?;
+
+    let last_run = sqlx::query_as!(
+        LastRun,
+        "SELECT run_id, status, started_at, finished_at FROM group_runs WHERE group_id = $1 ORDER BY started_at DESC LIMIT 1",
+        group_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(GroupDetail {
+        group_id: group.group_id,
+        group_name: group.group_name,
+        created_at: group.created_at,
+        paused: group.paused,
+        max_parallelism: group.max_parallelism,
+        job_count: group.job_count,
+        last_run,
+    }))
+}
+
+/// Request body for updating a group; omitted fields are left unchanged.
+///
+/// `max_parallelism` can be cleared with `null`.
+#[derive(Deserialize)]
+pub struct UpdateGroupRequest {
+    group_name: Option<String>,
+    #[serde(default, deserialize_with = "cron::double_option")]
+    max_parallelism: Option<Option<i32>>,
+}
+
+/// Handler to rename a group or change its `max_parallelism`.
+pub async fn update_job_group(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+    Json(payload): Json<UpdateGroupRequest>,
+) -> Result<Json<CreateGroupResponse>, (StatusCode, String)> {
+    validate_max_parallelism(payload.max_parallelism.flatten())
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    let record = sqlx::query!(
+        r#"
+        UPDATE job_groups
+        SET group_name = COALESCE($2, group_name),
+            max_parallelism = CASE WHEN $3 THEN $4 ELSE max_parallelism END
+        WHERE group_id = $1
+        RETURNING group_id, group_name, max_parallelism
+        "#,
+        group_id,
+        payload.group_name,
+        payload.max_parallelism.is_some(),
+        payload.max_parallelism.flatten()
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, format!("group {} not found", group_id)))?;
+
+    Ok(Json(CreateGroupResponse {
+        group_id: record.group_id,
+        group_name: record.group_name,
+        max_parallelism: record.max_parallelism,
+    }))
+}
+
+/// Handler to pause a group; paused groups are neither scheduled nor executed.
+pub async fn pause_job_group(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    set_group_paused(&pool, group_id, true).await
+}
+
+/// Handler to resume a paused group.
+pub async fn resume_job_group(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    set_group_paused(&pool, group_id, false).await
+}
+
+async fn set_group_paused(
+    pool: &PgPool,
+    group_id: Uuid,
+    paused: bool,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let updated = sqlx::query!(
+        "UPDATE job_groups SET paused = $2 WHERE group_id = $1",
+        group_id,
+        paused
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if updated.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, format!("group {} not found", group_id)));
+    }
+    Ok(Json(serde_json::json!({ "group_id": group_id, "paused": paused })))
+}
+
+#[derive(Deserialize)]
+pub struct DeleteGroupQuery {
+    force: Option<bool>,
+}
+
+/// Handler to delete a group and, through the FK cascade, its jobs and history.
+///
+/// Refuses with 409 while a run of the group is in progress unless `?force=true`,
+/// in which case in-flight runs are cancelled first.
+pub async fn delete_job_group(
+    State(state): State<AppState>,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<DeleteGroupQuery>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let pool = &state.pool;
+    if state.runs.in_progress(group_id) && !query.force.unwrap_or(false) {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("group {} has a run in progress; use ?force=true to delete anyway", group_id),
+        ));
+    }
+    for run in state.runs.for_group(group_id) {
+        run.cancel(pool).await;
+    }
+
+    let deleted = sqlx::query!("DELETE FROM job_groups WHERE group_id = $1", group_id)
+        .execute(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if deleted.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, format!("group {} not found", group_id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
 // 425faa70-d201-457a-bc3f-7a93b077c86a
-// 2c52d2bb-e9a2-4580-82f0-d49748da5eda
\ No newline at end of file
+// 2c52d2bb-e9a2-4580-82f0-d49748da5eda
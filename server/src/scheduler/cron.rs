@@ -1,23 +1,30 @@
-use crate::scheduler::s3::run_group_jobs_with_command;
+use crate::scheduler::s3::{EpochState, JobStatusReport, run_group_jobs_with_command, set_job_status};
+use crate::scheduler::dag::{build_edges, describe_cycle, find_cycle, topological_sort, with_ancestors, with_descendants};
+use crate::scheduler::runs::{completed_in_past_run, start_group_run};
+use crate::scheduler::schedule::parse_schedule;
+use crate::state::AppState;
 use axum::{
-    extract::{Json, Path, State},
+    extract::{Json, Path, Query, State},
     http::StatusCode,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use sqlx::{FromRow, PgPool};
-use std::fs;
 use uuid::Uuid;
 
 /// Handler to execute all cron jobs for a group (stub: prints what would be scheduled)
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap};
 
 /// Represents a dependency for a cron job: parent job and required epoch.
+///
+/// Epoch 0 means the parent must complete earlier in the same run. Epoch N > 0
+/// means the parent must have completed in the Nth most recent run that
+/// attempted it (1 = its last run); otherwise the child is skipped for this run.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct DependencyEntry {
     pub parent_id: i32, // parent cron_job_id
-    pub epoch: i32,     // how many attempts before last success
+    pub epoch: i32,     // how many runs back the parent must have succeeded
 }
 
 /// Request body for adding a cron job to a group.
@@ -25,9 +32,15 @@ pub struct DependencyEntry {
 pub struct AddCronJobRequest {
     pub cron_job_name: String,
     pub timings: DateTime<Utc>,
+    pub schedule: Option<String>, // cron expression, 5 or 6 fields
     pub children_names: Option<Vec<String>>, // downstream jobs (by name)
     pub dependencies_names: Option<Vec<(String, i32)>>, // (parent_name, epoch)
-    pub s3_link: Option<String>,
+    pub s3_link: Option<String>,          // a Dockerfile, or a .tar/.tar.gz build context
+    pub dockerfile_path: Option<String>,  // Dockerfile inside the build context, default "Dockerfile"
+    pub max_retries: Option<i32>,         // extra attempts after a failure, default 0
+    pub retry_delay_seconds: Option<i32>, // delay before the first retry, default 0
+    pub backoff_multiplier: Option<f64>,  // delay growth factor per retry, default 2.0
+    pub timeout_seconds: Option<i32>,     // kill the container after this long, default none
 }
 
 /// Response body after adding a cron job.
@@ -40,14 +53,32 @@ pub struct AddCronJobResponse {
 /// Handler to add a cron job to a group, storing DAG structure in the database.
 ///
 /// - group_id: The group to which the job belongs.
-/// - timings: Cron syntax for scheduling.
+/// - timings: Earliest time at which the job may run.
+/// - schedule: Optional cron expression; the background scheduler triggers the job on it.
 /// - children: List of downstream job IDs (edges in DAG).
 /// - dependencies: List of parent jobs and their required epochs.
+/// - max_retries / retry_delay_seconds / backoff_multiplier: Retry policy for failed attempts.
+/// - s3_link / dockerfile_path: Build input and the Dockerfile to use within it.
+/// - timeout_seconds: Optional limit on how long the container may run.
 pub async fn add_cron_job(
     State(pool): State<PgPool>,
     Path(group_id): Path<Uuid>,
     Json(payload): Json<AddCronJobRequest>,
 ) -> Result<Json<AddCronJobResponse>, (StatusCode, String)> {
+    let max_retries = payload.max_retries.unwrap_or(0);
+    let retry_delay_seconds = payload.retry_delay_seconds.unwrap_or(0);
+    let backoff_multiplier = payload.backoff_multiplier.unwrap_or(2.0);
+    validate_job_settings(
+        payload.schedule.as_deref(),
+        max_retries,
+        retry_delay_seconds,
+        backoff_multiplier,
+        payload.timeout_seconds,
+        payload.dockerfile_path.as_deref(),
+        payload.dependencies_names.as_deref(),
+    )
+    .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+
     // Resolve children by name to IDs
     let children: Vec<i32> = if let Some(names) = &payload.children_names {
         if names.is_empty() {
@@ -67,45 +98,69 @@ pub async fn add_cron_job(
         vec![]
     };
 
+    // Load the existing DAG to resolve parent names and check for cycles
+    let (existing, existing_deps) = load_group_graph(&pool, group_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let name_to_id: HashMap<&str, i32> = existing
+        .iter()
+        .map(|j| (j.cron_job_name.as_str(), j.cron_job_id))
+        .collect();
+    let parents: Vec<(i32, i32)> = payload
+        .dependencies_names
+        .iter()
+        .flatten()
+        .filter_map(|(name, epoch)| name_to_id.get(name.as_str()).map(|&id| (id, *epoch)))
+        .collect();
+
+    // The new job has no id yet; 0 is never assigned by the SERIAL column
+    const NEW_JOB: i32 = 0;
+    let mut edges = build_edges(&existing, &existing_deps);
+    edges.insert(NEW_JOB, children.clone());
+    for &(parent_id, epoch) in &parents {
+        if epoch == 0 {
+            edges.entry(parent_id).or_default().push(NEW_JOB);
+        }
+    }
+    let mut names = job_names(&existing);
+    names.insert(NEW_JOB, payload.cron_job_name.clone());
+    reject_cycle(&edges, &names, "adding job would create a cycle")?;
+
     // Insert the new job first (so it can be referenced by dependencies)
     let rec = sqlx::query!(
         r#"
-        INSERT INTO cron_jobs (group_id, cron_job_name, timings, children, s3_link)
-        VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO cron_jobs (group_id, cron_job_name, timings, schedule, children, s3_link,
+                               dockerfile_path, max_retries, retry_delay_seconds, backoff_multiplier,
+                               timeout_seconds)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
         RETURNING cron_job_id
         "#,
         group_id,
         payload.cron_job_name,
         payload.timings,
+        payload.schedule,
         &children[..],
         payload.s3_link,
+        payload.dockerfile_path,
+        max_retries,
+        retry_delay_seconds,
+        backoff_multiplier,
+        payload.timeout_seconds,
     )
     .fetch_one(&pool)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    .map_err(db_error)?;
 
-    // Insert dependencies by resolving parent names to IDs
-    if let Some(deps) = &payload.dependencies_names {
-        for (parent_name, epoch) in deps {
-            let parent = sqlx::query!(
-                "SELECT cron_job_id FROM cron_jobs WHERE group_id = $1 AND cron_job_name = $2",
-                group_id,
-                parent_name
-            )
-            .fetch_optional(&pool)
-            .await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
-            if let Some(parent) = parent {
-                let _ = sqlx::query!(
-                    "INSERT INTO cron_job_dependencies (cron_job_id, parent_id, epoch) VALUES ($1, $2, $3)",
-                    rec.cron_job_id,
-                    parent.cron_job_id,
-                    epoch
-                )
-                .execute(&pool)
-                .await;
-            }
-        }
+    // Insert dependencies using the parent IDs resolved above
+    for (parent_id, epoch) in parents {
+        let _ = sqlx::query!(
+            "INSERT INTO cron_job_dependencies (cron_job_id, parent_id, epoch) VALUES ($1, $2, $3)",
+            rec.cron_job_id,
+            parent_id,
+            epoch
+        )
+        .execute(&pool)
+        .await;
     }
 
     Ok(Json(AddCronJobResponse {
@@ -114,14 +169,330 @@ pub async fn add_cron_job(
     }))
 }
 
+/// Reject job settings the executor cannot honour.
+pub(crate) fn validate_job_settings(
+    schedule: Option<&str>,
+    max_retries: i32,
+    retry_delay_seconds: i32,
+    backoff_multiplier: f64,
+    timeout_seconds: Option<i32>,
+    dockerfile_path: Option<&str>,
+    dependencies: Option<&[(String, i32)]>,
+) -> Result<(), String> {
+    if let Some(expr) = schedule {
+        parse_schedule(expr)?;
+    }
+    if max_retries < 0 {
+        return Err("max_retries must not be negative".to_string());
+    }
+    if retry_delay_seconds < 0 {
+        return Err("retry_delay_seconds must not be negative".to_string());
+    }
+    if !backoff_multiplier.is_finite() || backoff_multiplier < 1.0 {
+        return Err("backoff_multiplier must be at least 1.0".to_string());
+    }
+    if timeout_seconds.is_some_and(|t| t <= 0) {
+        return Err("timeout_seconds must be positive".to_string());
+    }
+    if let Some(path) = dockerfile_path {
+        let path = std::path::Path::new(path);
+        let inside = path
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir));
+        if path.as_os_str().is_empty() || !inside {
+            return Err("dockerfile_path must be a relative path inside the build context".to_string());
+        }
+    }
+    if dependencies.is_some_and(|deps| deps.iter().any(|(_, epoch)| *epoch < 0)) {
+        return Err("dependency epoch must not be negative".to_string());
+    }
+    // A job depends on each parent at most once, whatever the epoch
+    let mut parents = std::collections::HashSet::new();
+    if let Some((name, _)) = dependencies.into_iter().flatten().find(|(name, _)| !parents.insert(name)) {
+        return Err(format!("dependency '{}' is listed more than once", name));
+    }
+    Ok(())
+}
+
+/// Map a database error to a response, reporting duplicate job names as a conflict.
+fn db_error(e: sqlx::Error) -> (StatusCode, String) {
+    if e.as_database_error().is_some_and(|d| d.is_unique_violation()) {
+        (StatusCode::CONFLICT, e.to_string())
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+    }
+}
+
+/// Load every job of a group together with their dependency rows.
+pub async fn load_group_graph(
+    pool: &PgPool,
+    group_id: Uuid,
+) -> Result<(Vec<CronJob>, Vec<CronJobDependency>), sqlx::Error> {
+    let jobs = sqlx::query_as::<_, CronJob>("SELECT * FROM cron_jobs WHERE group_id = $1")
+        .bind(group_id)
+        .fetch_all(pool)
+        .await?;
+    let deps = sqlx::query_as::<_, CronJobDependency>(
+        "SELECT * FROM cron_job_dependencies WHERE cron_job_id = ANY($1)",
+    )
+    .bind(jobs.iter().map(|j| j.cron_job_id).collect::<Vec<_>>())
+    .fetch_all(pool)
+    .await?;
+    Ok((jobs, deps))
+}
+
+/// Map job_id to name
+fn job_names(jobs: &[CronJob]) -> HashMap<i32, String> {
+    jobs.iter().map(|j| (j.cron_job_id, j.cron_job_name.clone())).collect()
+}
+
+/// Return a 422 naming the cycle path if `edges` contains a cycle.
+fn reject_cycle(edges: &HashMap<i32, Vec<i32>>, names: &HashMap<i32, String>, context: &str) -> Result<(), (StatusCode, String)> {
+    match find_cycle(edges) {
+        Some(cycle) => Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("{}: {}", context, describe_cycle(&cycle, names)),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Deserialize a field that may be absent (`None`), `null` (`Some(None)`) or set.
+pub(crate) fn double_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Request body for updating a cron job; omitted fields are left unchanged.
+///
+/// `schedule`, `s3_link`, `dockerfile_path` and `timeout_seconds` can be cleared with `null`.
+/// `children_names` and `dependencies_names` replace the job's edges when present.
+#[derive(Debug, Deserialize)]
+pub struct UpdateCronJobRequest {
+    pub cron_job_name: Option<String>,
+    pub timings: Option<DateTime<Utc>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub schedule: Option<Option<String>>,
+    pub children_names: Option<Vec<String>>,
+    pub dependencies_names: Option<Vec<(String, i32)>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub s3_link: Option<Option<String>>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub dockerfile_path: Option<Option<String>>,
+    pub max_retries: Option<i32>,
+    pub retry_delay_seconds: Option<i32>,
+    pub backoff_multiplier: Option<f64>,
+    #[serde(default, deserialize_with = "double_option")]
+    pub timeout_seconds: Option<Option<i32>>,
+}
+
+/// Handler to update a cron job in place.
+///
+/// Unlike `add_cron_job`, unknown children or dependency names are rejected,
+/// and the new edges are checked for cycles before anything is written.
+pub async fn update_cron_job(
+    State(pool): State<PgPool>,
+    Path((group_id, job_id)): Path<(Uuid, i32)>,
+    Json(payload): Json<UpdateCronJobRequest>,
+) -> Result<Json<CronJob>, (StatusCode, String)> {
+    let (jobs, deps) = load_group_graph(&pool, group_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut job = jobs
+        .iter()
+        .find(|j| j.cron_job_id == job_id)
+        .cloned()
+        .ok_or((StatusCode::NOT_FOUND, format!("job {} not found in group {}", job_id, group_id)))?;
+
+    if let Some(name) = payload.cron_job_name {
+        job.cron_job_name = name;
+    }
+    if let Some(timings) = payload.timings {
+        job.timings = timings;
+    }
+    if let Some(schedule) = payload.schedule {
+        job.schedule = schedule;
+    }
+    if let Some(s3_link) = payload.s3_link {
+        job.s3_link = s3_link;
+    }
+    if let Some(dockerfile_path) = payload.dockerfile_path {
+        job.dockerfile_path = dockerfile_path;
+    }
+    if let Some(timeout_seconds) = payload.timeout_seconds {
+        job.timeout_seconds = timeout_seconds;
+    }
+    job.max_retries = payload.max_retries.unwrap_or(job.max_retries);
+    job.retry_delay_seconds = payload.retry_delay_seconds.unwrap_or(job.retry_delay_seconds);
+    job.backoff_multiplier = payload.backoff_multiplier.unwrap_or(job.backoff_multiplier);
+    validate_job_settings(
+        job.schedule.as_deref(),
+        job.max_retries,
+        job.retry_delay_seconds,
+        job.backoff_multiplier,
+        job.timeout_seconds,
+        job.dockerfile_path.as_deref(),
+        payload.dependencies_names.as_deref(),
+    )
+    .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+
+    // Resolve names strictly so a typo cannot silently drop an edge
+    let name_to_id: HashMap<&str, i32> = jobs
+        .iter()
+        .map(|j| (j.cron_job_name.as_str(), j.cron_job_id))
+        .collect();
+    let resolve = |name: &str| {
+        name_to_id
+            .get(name)
+            .copied()
+            .ok_or((StatusCode::UNPROCESSABLE_ENTITY, format!("unknown job '{}'", name)))
+    };
+    if let Some(names) = &payload.children_names {
+        job.children = Some(names.iter().map(|n| resolve(n)).collect::<Result<_, _>>()?);
+    }
+    let parents: Option<Vec<(i32, i32)>> = payload
+        .dependencies_names
+        .as_ref()
+        .map(|deps| {
+            deps.iter()
+                .map(|(name, epoch)| resolve(name).map(|id| (id, *epoch)))
+                .collect::<Result<_, _>>()
+        })
+        .transpose()?;
+
+    // Check the graph as it would look after the update
+    let graph_jobs: Vec<CronJob> = jobs
+        .iter()
+        .map(|j| if j.cron_job_id == job_id { job.clone() } else { j.clone() })
+        .collect();
+    let graph_deps: Vec<CronJobDependency> = match &parents {
+        Some(parents) => deps
+            .iter()
+            .filter(|d| d.cron_job_id != job_id)
+            .copied()
+            .chain(parents.iter().map(|&(parent_id, epoch)| CronJobDependency {
+                cron_job_id: job_id,
+                parent_id,
+                epoch,
+            }))
+            .collect(),
+        None => deps.clone(),
+    };
+    reject_cycle(
+        &build_edges(&graph_jobs, &graph_deps),
+        &job_names(&graph_jobs),
+        "updating job would create a cycle",
+    )?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    sqlx::query!(
+        r#"
+        UPDATE cron_jobs
+        SET cron_job_name = $3, timings = $4, schedule = $5, children = $6, s3_link = $7,
+            dockerfile_path = $8, max_retries = $9, retry_delay_seconds = $10,
+            backoff_multiplier = $11, timeout_seconds = $12
+        WHERE cron_job_id = $1 AND group_id = $2
+        "#,
+        job_id,
+        group_id,
+        job.cron_job_name,
+        job.timings,
+        job.schedule,
+        job.children.as_deref(),
+        job.s3_link,
+        job.dockerfile_path,
+        job.max_retries,
+        job.retry_delay_seconds,
+        job.backoff_multiplier,
+        job.timeout_seconds,
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error)?;
+
+    if let Some(parents) = parents {
+        sqlx::query!("DELETE FROM cron_job_dependencies WHERE cron_job_id = $1", job_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        for (parent_id, epoch) in parents {
+            sqlx::query!(
+                "INSERT INTO cron_job_dependencies (cron_job_id, parent_id, epoch) VALUES ($1, $2, $3)",
+                job_id,
+                parent_id,
+                epoch
+            )
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        }
+    }
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(job))
+}
+
+/// Handler to delete a cron job.
+///
+/// The FK cascade removes its dependency rows, status and run history, but not
+/// its id from other jobs' `children` arrays, so those are cleaned up here.
+pub async fn delete_cron_job(
+    State(pool): State<PgPool>,
+    Path((group_id, job_id)): Path<(Uuid, i32)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let deleted = sqlx::query!(
+        "DELETE FROM cron_jobs WHERE cron_job_id = $1 AND group_id = $2",
+        job_id,
+        group_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if deleted.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, format!("job {} not found in group {}", job_id, group_id)));
+    }
+    sqlx::query!(
+        "UPDATE cron_jobs SET children = array_remove(children, $1) WHERE group_id = $2 AND $1 = ANY(children)",
+        job_id,
+        group_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    tx.commit()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[derive(Debug, Deserialize, Serialize, FromRow, Clone)]
 pub struct CronJob {
     pub cron_job_id: i32,
     pub group_id: Uuid,
     pub cron_job_name: String,
     pub timings: DateTime<Utc>,
+    pub schedule: Option<String>,
     pub children: Option<Vec<i32>>,
     pub s3_link: Option<String>,
+    pub dockerfile_path: Option<String>,
+    pub max_retries: i32,
+    pub retry_delay_seconds: i32,
+    pub backoff_multiplier: f64,
+    pub timeout_seconds: Option<i32>,
+    pub paused: bool,
 }
 // List all groups
 pub async fn get_groups(State(pool): State<PgPool>) -> Result<Json<Vec<(Uuid, String)>>, (StatusCode, String)> {
@@ -138,9 +509,16 @@ pub struct JobWithNames {
     pub cron_job_id: i32,
     pub cron_job_name: String,
     pub timings: DateTime<Utc>,
+    pub schedule: Option<String>,
     pub children: Vec<String>,
     pub dependencies: Vec<String>,
     pub s3_link: Option<String>,
+    pub dockerfile_path: Option<String>,
+    pub max_retries: i32,
+    pub retry_delay_seconds: i32,
+    pub backoff_multiplier: f64,
+    pub timeout_seconds: Option<i32>,
+    pub paused: bool,
 }
 
 pub async fn get_jobs_for_group(
@@ -196,9 +574,16 @@ pub async fn get_jobs_for_group(
                 cron_job_id: job.cron_job_id,
                 cron_job_name: job.cron_job_name.clone(),
                 timings: job.timings,
+                schedule: job.schedule.clone(),
                 children,
                 dependencies,
                 s3_link: job.s3_link.clone(),
+                dockerfile_path: job.dockerfile_path.clone(),
+                max_retries: job.max_retries,
+                retry_delay_seconds: job.retry_delay_seconds,
+                backoff_multiplier: job.backoff_multiplier,
+                timeout_seconds: job.timeout_seconds,
+                paused: job.paused,
             }
         })
         .collect();
@@ -212,146 +597,348 @@ pub struct CronJobDependency {
     pub epoch: i32,
 }
 
-/// Topological sort using Kahn's algorithm
-fn topological_sort(jobs: &[CronJob]) -> Vec<i32> {
-    let mut in_degree: HashMap<i32, usize> = HashMap::new();
-    let mut graph: HashMap<i32, Vec<i32>> = HashMap::new();
-    let mut all_ids: std::collections::HashSet<i32> = std::collections::HashSet::new();
-    for job in jobs {
-        all_ids.insert(job.cron_job_id);
-        in_degree.entry(job.cron_job_id).or_insert(0);
-        if let Some(children) = &job.children {
-            for &child in children {
-                all_ids.insert(child);
-                *in_degree.entry(child).or_insert(0) += 1;
-                graph.entry(job.cron_job_id).or_default().push(child);
-            }
-        }
-    }
-    // Ensure all nodes are present in in_degree
-    for &id in &all_ids {
-        in_degree.entry(id).or_insert(0);
-    }
-    let mut queue: VecDeque<i32> = in_degree
-        .iter()
-        .filter(|item| *item.1 == 0)
-        .map(|item| *item.0)
-        .collect();
-    let mut order = Vec::new();
-    let mut visited: std::collections::HashSet<i32> = std::collections::HashSet::new();
-    while let Some(id) = queue.pop_front() {
-        if !visited.insert(id) {
-            continue;
-        }
-        order.push(id);
-        if let Some(children) = graph.get(&id) {
-            for &child in children {
-                if let Some(e) = in_degree.get_mut(&child) {
-                    if *e > 0 {
-                        *e -= 1;
-                        if *e == 0 {
-                            queue.push_back(child);
-                        }
-                    }
-                }
-            }
-        }
-    }
-    // If there are nodes not in order, append them (to avoid missing jobs)
-    for &id in &all_ids {
-        if !order.contains(&id) {
-            order.push(id);
-        }
-    }
-    order
+/// Query parameters for executing a group.
+#[derive(Debug, Deserialize)]
+pub struct ExecuteQuery {
+    pub rebuild: Option<bool>, // rebuild images even if the build input is unchanged
 }
+
+#[tracing::instrument(skip_all, fields(%group_id))]
 pub async fn execute_cron_jobs_for_group(
-    State(pool): State<PgPool>,
+    State(state): State<AppState>,
     Path(group_id): Path<Uuid>,
-) -> Result<String, String> {
-    println!("{:?}", pool);
-    println!("Executing cron jobs for group: {}", group_id);
-    let jobs = sqlx::query_as::<_, CronJob>(r#"SELECT * FROM cron_jobs WHERE group_id = $1"#)
-        .bind(group_id)
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())).unwrap();
+    Query(query): Query<ExecuteQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let report = execute_group(&state, group_id, None, query.rebuild.unwrap_or(false)).await?;
 
-    println!("Got all the jobs: {:?}", jobs);
+    Ok(Json(json!({
+        "run_id": report.run_id,
+        "running": report.running,
+        "pending": report.pending,
+        "skipped": report.skipped,
+    })))
+}
 
-    // Build job_id -> job map
-    let jobs_map: HashMap<i32, CronJob> = jobs.iter().cloned().map(|j| (j.cron_job_id, j)).collect();
-    
-    // Load all dependencies for jobs in this group
-    let deps: Vec<CronJobDependency> = sqlx::query_as::<_, CronJobDependency>(
-        "SELECT * FROM cron_job_dependencies WHERE cron_job_id = ANY($1)",
+/// Load a group's DAG and start its jobs.
+///
+/// `roots` restricts the run to those jobs (used by the cron scheduler), their
+/// dependents, and the same-run parents any of them wait on.
+/// `None` starts every job whose dependencies are satisfied.
+/// `rebuild` skips the image cache for every job in the run.
+/// A group whose DAG contains a cycle is rejected without starting a run.
+#[tracing::instrument(name = "group_run", skip(state), fields(run_id = tracing::field::Empty))]
+pub async fn execute_group(
+    state: &AppState,
+    group_id: Uuid,
+    roots: Option<Vec<i32>>,
+    rebuild: bool,
+) -> Result<JobStatusReport, (StatusCode, String)> {
+    let pool = &state.pool;
+    // Share-lock the group until the run is registered, so a definition import
+    // (which locks it FOR UPDATE) either finishes first or sees this run
+    let mut group_lock = pool
+        .begin()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let group = sqlx::query!(
+        "SELECT paused, max_parallelism FROM job_groups WHERE group_id = $1 FOR SHARE",
+        group_id
     )
-    .bind(jobs.iter().map(|j| j.cron_job_id).collect::<Vec<_>>())
-    .fetch_all(&pool)
+    .fetch_optional(&mut *group_lock)
     .await
-    .unwrap_or_default();
-
-    let mut deps_map: HashMap<i32, Vec<CronJobDependency>> = HashMap::new();
-    for dep in &deps {
-        deps_map.entry(dep.cron_job_id).or_default().push(*dep);
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, format!("group {} not found", group_id)))?;
+    if group.paused {
+        return Err((StatusCode::CONFLICT, format!("group {} is paused", group_id)));
     }
+    // Load all jobs and dependencies for this group
+    let (jobs, deps) = load_group_graph(pool, group_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    // Build job_id -> job map
+    let jobs_map: HashMap<i32, CronJob> = jobs.iter().cloned().map(|j| (j.cron_job_id, j)).collect();
 
     // Topological sort to get execution order
-    let order = topological_sort(&jobs);
-    println!("{:?}", order);
-    println!("Dependencies: {:?}", deps_map);
-    // Build dependency map: job_id -> Vec<parent_id>
+    let mut order = topological_sort(&build_edges(&jobs, &deps)).map_err(|cycle| {
+        (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("group contains a cycle: {}", describe_cycle(&cycle, &job_names(&jobs))),
+        )
+    })?;
+    // Children arrays may still name jobs that no longer exist
+    order.retain(|id| jobs_map.contains_key(id));
+    // Build dependency map: job_id -> Vec<parent_id>, for parents in this same run
     let mut dependency_map: HashMap<i32, Vec<i32>> = HashMap::new();
-    for dep in &deps {
+    for dep in deps.iter().filter(|d| d.epoch == 0) {
         dependency_map.entry(dep.cron_job_id).or_default().push(dep.parent_id);
     }
-    println!("Dependency map: {:?}", dependency_map);
-    // Use a shared epoch state for this group
-    let epoch_state = crate::scheduler::s3::EpochState::default();
+    if let Some(roots) = &roots {
+        // Due jobs and their dependents wait on same-run parents, so those join the
+        // run even when not due themselves; jobs outside that lineage stay out
+        let lineage = with_ancestors(&with_descendants(roots, &dependency_map), &dependency_map);
+        order.retain(|id| lineage.contains(id));
+        dependency_map.retain(|id, _| lineage.contains(id));
+    }
+    tracing::debug!(?order, ?dependency_map, "resolved execution order");
+
+    // Paused jobs, and jobs whose dependencies on earlier runs are unmet, are skipped
+    let in_run = with_descendants(&order, &dependency_map);
+    let mut skipped: Vec<i32> = jobs
+        .iter()
+        .filter(|j| j.paused && in_run.contains(&j.cron_job_id))
+        .map(|j| j.cron_job_id)
+        .collect();
+    for dep in deps.iter().filter(|d| d.epoch > 0 && in_run.contains(&d.cron_job_id)) {
+        if skipped.contains(&dep.cron_job_id) {
+            continue;
+        }
+        let satisfied = completed_in_past_run(pool, group_id, dep.parent_id, dep.epoch)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        if !satisfied {
+            tracing::info!(
+                job_id = dep.cron_job_id,
+                parent_id = dep.parent_id,
+                epoch = dep.epoch,
+                "skipping job: parent did not complete in that earlier run"
+            );
+            skipped.push(dep.cron_job_id);
+        }
+    }
+
+    // Every execution gets its own run record and epoch state
+    let run_id = start_group_run(pool, group_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    tracing::Span::current().record("run_id", tracing::field::display(run_id));
+    tracing::info!(jobs = order.len(), "starting group run");
+    let epoch_state = EpochState::new(
+        run_id,
+        group_id,
+        rebuild,
+        group.max_parallelism.map(|n| n as usize),
+        state,
+    );
+    let _ = group_lock.commit().await;
+    // Jobs downstream of a skipped job can never start in this run, so skip them too
+    for job_id in with_descendants(&skipped, &dependency_map) {
+        epoch_state.skip(job_id);
+        set_job_status(pool, job_id, group_id, "skipped").await;
+    }
 
     // Run all jobs in group using the new function
-    let _status = run_group_jobs_with_command(
+    run_group_jobs_with_command(
         order,
         jobs_map,
         dependency_map,
-        &group_id.to_string(),
         epoch_state,
         pool.clone()
-    ).await;
-    println!("Jobs running");
+    )
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// Handler to pause a job; it is skipped by every run until resumed.
+pub async fn pause_cron_job(
+    State(pool): State<PgPool>,
+    Path((group_id, job_id)): Path<(Uuid, i32)>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    set_job_paused(&pool, group_id, job_id, true).await
+}
+
+/// Handler to resume a paused job.
+pub async fn resume_cron_job(
+    State(pool): State<PgPool>,
+    Path((group_id, job_id)): Path<(Uuid, i32)>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    set_job_paused(&pool, group_id, job_id, false).await
+}
 
-    Ok("Executed Successfully".to_string())
+async fn set_job_paused(
+    pool: &PgPool,
+    group_id: Uuid,
+    job_id: i32,
+    paused: bool,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let updated = sqlx::query!(
+        "UPDATE cron_jobs SET paused = $3 WHERE cron_job_id = $1 AND group_id = $2",
+        job_id,
+        group_id,
+        paused
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if updated.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, format!("job {} not found in group {}", job_id, group_id)));
+    }
+    Ok(Json(json!({ "job_id": job_id, "group_id": group_id, "paused": paused })))
 }
 
 /// Handler to get the status and log file of a job
+///
+/// The log itself is served by `get_cron_job_logs`; `logs` points at it. A job
+/// that has never run has a null `status`.
 pub async fn get_cron_job_status(
     State(pool): State<PgPool>,
     Path((group_id, job_id)): Path<(Uuid, i32)>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
-    // Query job_status table for this job
+    // Query the job and its job_status row, if any
     let rec = sqlx::query!(
-        "SELECT status, updated_at FROM job_status WHERE cron_job_id = $1 AND group_id = $2",
+        r#"
+        SELECT s.status AS "status?", s.updated_at AS "updated_at?"
+        FROM cron_jobs j
+        LEFT JOIN job_status s ON s.cron_job_id = j.cron_job_id AND s.group_id = j.group_id
+        WHERE j.cron_job_id = $1 AND j.group_id = $2
+        "#,
         job_id,
         group_id
     )
     .fetch_optional(&pool)
     .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, format!("job {} not found in group {}", job_id, group_id)))?;
+
+    Ok(Json(json!({
+        "job_id": job_id,
+        "group_id": group_id,
+        "status": rec.status,
+        "updated_at": rec.updated_at,
+        "logs": format!("/cron_job_logs/{}/{}", group_id, job_id)
+    })))
+}
+
+/// Current state of one job within `GroupStatus`.
+#[derive(Debug, Serialize)]
+pub struct JobStatusSummary {
+    pub job_id: i32,
+    pub job_name: String,
+    pub paused: bool,
+    pub status: Option<String>, // None if the job has never run
+    pub updated_at: Option<DateTime<Utc>>,
+    pub attempts: i64,       // attempts in the group's latest run
+    pub total_attempts: i64, // attempts across all runs
+    pub last_started_at: Option<DateTime<Utc>>, // of the job's latest attempt
+    pub last_finished_at: Option<DateTime<Utc>>,
+    pub last_exit_code: Option<i32>,
+}
+
+/// The group's latest run.
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub run_id: Uuid,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Response body for `get_group_status`.
+#[derive(Debug, Serialize)]
+pub struct GroupStatus {
+    pub group_id: Uuid,
+    pub group_name: String,
+    pub paused: bool,
+    pub run: Option<RunSummary>,
+    pub counts: BTreeMap<String, usize>, // jobs per status; never run jobs count as 'not_run'
+    pub jobs: Vec<JobStatusSummary>,
+}
+
+/// Handler to get the status of every job in a group together with its latest run.
+///
+/// Everything is read in one query so the jobs and the run are consistent.
+pub async fn get_group_status(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<GroupStatus>, (StatusCode, String)> {
+    // One row per job; a group without jobs still yields one row with null job columns
+    let rows = sqlx::query!(
+        r#"
+        WITH latest_run AS (
+            SELECT run_id, status, started_at, finished_at FROM group_runs
+            WHERE group_id = $1
+            ORDER BY started_at DESC
+            LIMIT 1
+        ),
+        attempts AS (
+            SELECT jr.cron_job_id,
+                   COUNT(*) FILTER (WHERE jr.run_id = (SELECT run_id FROM latest_run)) AS run_attempts,
+                   COUNT(*) AS total_attempts
+            FROM job_runs jr
+            JOIN group_runs gr ON gr.run_id = jr.run_id
+            WHERE gr.group_id = $1
+            GROUP BY jr.cron_job_id
+        ),
+        last_attempt AS (
+            SELECT DISTINCT ON (jr.cron_job_id) jr.cron_job_id, jr.started_at, jr.finished_at, jr.exit_code
+            FROM job_runs jr
+            JOIN group_runs gr ON gr.run_id = jr.run_id
+            WHERE gr.group_id = $1
+            ORDER BY jr.cron_job_id, jr.started_at DESC, jr.job_run_id DESC
+        )
+        SELECT g.group_name, g.paused AS group_paused,
+               r.run_id AS "run_id?", r.status AS "run_status?",
+               r.started_at AS "run_started_at?", r.finished_at AS "run_finished_at?",
+               j.cron_job_id AS "job_id?", j.cron_job_name AS "job_name?", j.paused AS "job_paused?",
+               s.status AS "status?", s.updated_at AS "updated_at?",
+               COALESCE(a.run_attempts, 0) AS "attempts!", COALESCE(a.total_attempts, 0) AS "total_attempts!",
+               l.started_at AS "last_started_at?", l.finished_at AS "last_finished_at?", l.exit_code AS "last_exit_code?"
+        FROM job_groups g
+        LEFT JOIN latest_run r ON TRUE
+        LEFT JOIN cron_jobs j ON j.group_id = g.group_id
+        LEFT JOIN job_status s ON s.cron_job_id = j.cron_job_id AND s.group_id = g.group_id
+        LEFT JOIN attempts a ON a.cron_job_id = j.cron_job_id
+        LEFT JOIN last_attempt l ON l.cron_job_id = j.cron_job_id
+        WHERE g.group_id = $1
+        ORDER BY j.cron_job_id
+        "#,
+        group_id
+    )
+    .fetch_all(&pool)
+    .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    if let Some(row) = rec {
-        Ok(Json(json!({
-            "job_id": job_id,
-            "group_id": group_id,
-            "status": row.status,
-            "updated_at": row.updated_at
-        })))
-    } else {
-        Ok(Json(json!({
-            "job_id": job_id,
-            "group_id": group_id,
-            "status": "not found"
-        })))
+    let first = rows
+        .first()
+        .ok_or((StatusCode::NOT_FOUND, format!("group {} not found", group_id)))?;
+    let run = match (first.run_id, &first.run_status, first.run_started_at) {
+        (Some(run_id), Some(status), Some(started_at)) => Some(RunSummary {
+            run_id,
+            status: status.clone(),
+            started_at,
+            finished_at: first.run_finished_at,
+        }),
+        _ => None,
+    };
+    let (group_name, paused) = (first.group_name.clone(), first.group_paused);
+
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut jobs = Vec::new();
+    for row in rows {
+        let (Some(job_id), Some(job_name)) = (row.job_id, row.job_name) else { continue };
+        *counts.entry(row.status.clone().unwrap_or_else(|| "not_run".to_string())).or_default() += 1;
+        jobs.push(JobStatusSummary {
+            job_id,
+            job_name,
+            paused: row.job_paused.unwrap_or(false),
+            status: row.status,
+            updated_at: row.updated_at,
+            attempts: row.attempts,
+            total_attempts: row.total_attempts,
+            last_started_at: row.last_started_at,
+            last_finished_at: row.last_finished_at,
+            last_exit_code: row.last_exit_code,
+        });
     }
+
+    Ok(Json(GroupStatus {
+        group_id,
+        group_name,
+        paused,
+        run,
+        counts,
+        jobs,
+    }))
 }
 
 // b8f32ccc-1f38-4a7a-baba-852f3dcd562c
@@ -0,0 +1,202 @@
+use crate::scheduler::cron::{CronJob, CronJobDependency};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Build the execution edges of a group: parent_id -> Vec<child_id>.
+///
+/// Edges come from both `cron_jobs.children` and same-run (epoch 0) rows of
+/// `cron_job_dependencies`. Dependencies on earlier runs do not order jobs
+/// within a run, so they are not edges here.
+pub fn build_edges(jobs: &[CronJob], deps: &[CronJobDependency]) -> HashMap<i32, Vec<i32>> {
+    let mut edges: HashMap<i32, Vec<i32>> = HashMap::new();
+    for job in jobs {
+        edges.entry(job.cron_job_id).or_default();
+        for &child in job.children.iter().flatten() {
+            edges.entry(job.cron_job_id).or_default().push(child);
+        }
+    }
+    for dep in deps.iter().filter(|d| d.epoch == 0) {
+        edges.entry(dep.parent_id).or_default().push(dep.cron_job_id);
+    }
+    for children in edges.values_mut() {
+        children.sort_unstable();
+        children.dedup();
+    }
+    edges
+}
+
+/// Find a cycle in `edges`, returned as the path of node ids with the first
+/// node repeated at the end (e.g. `[a, b, c, a]`).
+pub fn find_cycle(edges: &HashMap<i32, Vec<i32>>) -> Option<Vec<i32>> {
+    fn visit(
+        node: i32,
+        edges: &HashMap<i32, Vec<i32>>,
+        done: &mut HashSet<i32>,
+        path: &mut Vec<i32>,
+    ) -> Option<Vec<i32>> {
+        if let Some(pos) = path.iter().position(|&n| n == node) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(node);
+            return Some(cycle);
+        }
+        if done.contains(&node) {
+            return None;
+        }
+        path.push(node);
+        for &child in edges.get(&node).into_iter().flatten() {
+            if let Some(cycle) = visit(child, edges, done, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        done.insert(node);
+        None
+    }
+
+    let mut nodes: Vec<i32> = edges.keys().copied().collect();
+    nodes.sort_unstable();
+    let mut done = HashSet::new();
+    for node in nodes {
+        if let Some(cycle) = visit(node, edges, &mut done, &mut Vec::new()) {
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+/// `roots` plus every job downstream of them, given job_id -> Vec<parent_id>.
+pub fn with_descendants(roots: &[i32], dependencies: &HashMap<i32, Vec<i32>>) -> HashSet<i32> {
+    let mut seen: HashSet<i32> = HashSet::new();
+    let mut queue: Vec<i32> = roots.to_vec();
+    while let Some(id) = queue.pop() {
+        if seen.insert(id) {
+            queue.extend(
+                dependencies
+                    .iter()
+                    .filter(|(_, parents)| parents.contains(&id))
+                    .map(|(&child, _)| child),
+            );
+        }
+    }
+    seen
+}
+
+/// `jobs` plus every job upstream of them, given job_id -> Vec<parent_id>.
+pub fn with_ancestors(jobs: &HashSet<i32>, dependencies: &HashMap<i32, Vec<i32>>) -> HashSet<i32> {
+    let mut seen: HashSet<i32> = HashSet::new();
+    let mut queue: Vec<i32> = jobs.iter().copied().collect();
+    while let Some(id) = queue.pop() {
+        if seen.insert(id) {
+            queue.extend(dependencies.get(&id).into_iter().flatten());
+        }
+    }
+    seen
+}
+
+/// Render a cycle path using job names, e.g. `a -> b -> a`.
+pub fn describe_cycle(cycle: &[i32], names: &HashMap<i32, String>) -> String {
+    cycle
+        .iter()
+        .map(|id| names.get(id).cloned().unwrap_or_else(|| id.to_string()))
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Topological sort using Kahn's algorithm.
+///
+/// Returns the cycle path if the graph cannot be ordered.
+pub fn topological_sort(edges: &HashMap<i32, Vec<i32>>) -> Result<Vec<i32>, Vec<i32>> {
+    let mut in_degree: HashMap<i32, usize> = edges.keys().map(|&id| (id, 0)).collect();
+    for children in edges.values() {
+        for &child in children {
+            *in_degree.entry(child).or_insert(0) += 1;
+        }
+    }
+    let mut roots: Vec<i32> = in_degree
+        .iter()
+        .filter(|item| *item.1 == 0)
+        .map(|item| *item.0)
+        .collect();
+    roots.sort_unstable();
+    let mut queue: VecDeque<i32> = roots.into();
+    let mut order = Vec::new();
+    while let Some(id) = queue.pop_front() {
+        order.push(id);
+        for &child in edges.get(&id).into_iter().flatten() {
+            if let Some(e) = in_degree.get_mut(&child) {
+                *e -= 1;
+                if *e == 0 {
+                    queue.push_back(child);
+                }
+            }
+        }
+    }
+    if order.len() < in_degree.len() {
+        return Err(find_cycle(edges).unwrap_or_default());
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edges(pairs: &[(i32, i32)]) -> HashMap<i32, Vec<i32>> {
+        let mut edges: HashMap<i32, Vec<i32>> = HashMap::new();
+        for &(from, to) in pairs {
+            edges.entry(from).or_default().push(to);
+            edges.entry(to).or_default();
+        }
+        edges
+    }
+
+    fn names(pairs: &[(i32, &str)]) -> HashMap<i32, String> {
+        pairs.iter().map(|&(id, name)| (id, name.to_string())).collect()
+    }
+
+    #[test]
+    fn self_loop_is_a_cycle() {
+        let graph = edges(&[(1, 2), (2, 2)]);
+        assert_eq!(find_cycle(&graph), Some(vec![2, 2]));
+        assert_eq!(topological_sort(&graph), Err(vec![2, 2]));
+        assert_eq!(describe_cycle(&[2, 2], &names(&[(2, "b")])), "b -> b");
+    }
+
+    #[test]
+    fn three_node_cycle_is_reported_as_a_path() {
+        let graph = edges(&[(0, 1), (1, 2), (2, 3), (3, 1)]);
+        let cycle = find_cycle(&graph).unwrap();
+        assert_eq!(cycle, vec![1, 2, 3, 1]);
+        assert_eq!(topological_sort(&graph), Err(cycle.clone()));
+        assert_eq!(
+            describe_cycle(&cycle, &names(&[(1, "extract"), (2, "transform"), (3, "load")])),
+            "extract -> transform -> load -> extract"
+        );
+    }
+
+    #[test]
+    fn acyclic_graph_is_ordered_parents_first() {
+        let graph = edges(&[(1, 3), (2, 3), (3, 4), (1, 4), (5, 6)]);
+        assert_eq!(find_cycle(&graph), None);
+        let order = topological_sort(&graph).unwrap();
+        assert_eq!(order.len(), 6);
+        let position = |id: i32| order.iter().position(|&n| n == id).unwrap();
+        for (&parent, children) in &graph {
+            for &child in children {
+                assert!(position(parent) < position(child), "{} before {} in {:?}", parent, child, order);
+            }
+        }
+    }
+
+    #[test]
+    fn describe_cycle_falls_back_to_ids() {
+        assert_eq!(describe_cycle(&[7, 8, 7], &names(&[(7, "a")])), "a -> 8 -> a");
+    }
+
+    #[test]
+    fn descendants_and_ancestors_follow_dependencies() {
+        // job_id -> parents: 2 and 3 depend on 1, 4 on 3
+        let dependencies: HashMap<i32, Vec<i32>> = HashMap::from([(2, vec![1]), (3, vec![1]), (4, vec![3])]);
+        assert_eq!(with_descendants(&[3], &dependencies), HashSet::from([3, 4]));
+        assert_eq!(with_ancestors(&HashSet::from([4]), &dependencies), HashSet::from([1, 3, 4]));
+    }
+}
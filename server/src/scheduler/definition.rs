@@ -0,0 +1,554 @@
+use crate::scheduler::cron::{load_group_graph, validate_job_settings};
+use crate::scheduler::dag::{describe_cycle, find_cycle};
+use crate::scheduler::validate_max_parallelism;
+use crate::state::AppState;
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgPool, Postgres, Transaction};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// A whole group as one document; the format both imported and exported.
+///
+/// Jobs are identified by name. Edges name other jobs of the same document, so
+/// jobs can be listed in any order.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct GroupDefinition {
+    pub group_name: String,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_parallelism: Option<i32>,
+    #[serde(default)]
+    pub jobs: Vec<JobDefinition>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct JobDefinition {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timings: Option<DateTime<Utc>>, // earliest run time; defaults to the time of import
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3_link: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dockerfile_path: Option<String>,
+    #[serde(default)]
+    pub max_retries: i32,
+    #[serde(default)]
+    pub retry_delay_seconds: i32,
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<i32>,
+    #[serde(default)]
+    pub paused: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<String>, // names of jobs that run after this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<DependencyDefinition>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct DependencyDefinition {
+    pub job: String, // parent job name
+    #[serde(default)]
+    pub epoch: i32, // 0: same run; N: parent must have completed in its Nth latest run
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+/// What importing a definition changed, by job name.
+#[derive(Debug, Serialize)]
+pub struct ImportSummary {
+    pub group_id: Uuid,
+    pub dry_run: bool,
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+/// Query parameters for importing a definition.
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    pub dry_run: Option<bool>, // validate and report changes without applying them
+}
+
+/// Query parameters for exporting a definition.
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<String>, // 'json' (default) or 'yaml'
+}
+
+/// Parse a definition as YAML or JSON according to the request's content type.
+fn parse_definition(headers: &HeaderMap, body: &str) -> Result<GroupDefinition, (StatusCode, String)> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let parsed = if content_type.contains("yaml") {
+        serde_yaml_ng::from_str(body).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(body).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid group definition: {}", e)))
+}
+
+/// Check the document as a whole, returning every problem found.
+fn validate_definition(definition: &GroupDefinition) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    if definition.group_name.trim().is_empty() {
+        errors.push("group_name must not be empty".to_string());
+    }
+    if let Err(e) = validate_max_parallelism(definition.max_parallelism) {
+        errors.push(e);
+    }
+
+    // Job names index the graph below
+    let mut index: HashMap<&str, i32> = HashMap::new();
+    for (i, job) in definition.jobs.iter().enumerate() {
+        if job.name.trim().is_empty() {
+            errors.push(format!("job #{} has an empty name", i + 1));
+        } else if index.insert(job.name.as_str(), i as i32).is_some() {
+            errors.push(format!("job '{}' is defined more than once", job.name));
+        }
+    }
+
+    let mut edges: HashMap<i32, Vec<i32>> = HashMap::new();
+    for (i, job) in definition.jobs.iter().enumerate() {
+        let id = i as i32;
+        let dependencies: Vec<(String, i32)> = job
+            .dependencies
+            .iter()
+            .map(|d| (d.job.clone(), d.epoch))
+            .collect();
+        if let Err(e) = validate_job_settings(
+            job.schedule.as_deref(),
+            job.max_retries,
+            job.retry_delay_seconds,
+            job.backoff_multiplier,
+            job.timeout_seconds,
+            job.dockerfile_path.as_deref(),
+            Some(&dependencies),
+        ) {
+            errors.push(format!("job '{}': {}", job.name, e));
+        }
+
+        edges.entry(id).or_default();
+        for child in &job.children {
+            match index.get(child.as_str()) {
+                Some(&child_id) if child_id == id => errors.push(format!("job '{}' lists itself as a child", job.name)),
+                Some(&child_id) => edges.entry(id).or_default().push(child_id),
+                None => errors.push(format!("job '{}': unknown child '{}'", job.name, child)),
+            }
+        }
+        for dependency in &job.dependencies {
+            match index.get(dependency.job.as_str()) {
+                Some(&parent_id) if parent_id == id => errors.push(format!("job '{}' depends on itself", job.name)),
+                Some(&parent_id) if dependency.epoch == 0 => edges.entry(parent_id).or_default().push(id),
+                Some(_) => {}
+                None => errors.push(format!("job '{}': unknown dependency '{}'", job.name, dependency.job)),
+            }
+        }
+    }
+
+    if let Some(cycle) = find_cycle(&edges) {
+        let names: HashMap<i32, String> = index.iter().map(|(&name, &id)| (id, name.to_string())).collect();
+        errors.push(format!("jobs form a cycle: {}", describe_cycle(&cycle, &names)));
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// Make the group's settings, jobs and edges match `definition`.
+///
+/// Jobs are matched by name: existing ones are updated in place, keeping their
+/// ids and history, missing ones are created and the rest are deleted.
+async fn apply_definition(
+    tx: &mut Transaction<'_, Postgres>,
+    group_id: Uuid,
+    definition: &GroupDefinition,
+) -> Result<ImportSummary, sqlx::Error> {
+    sqlx::query!(
+        "UPDATE job_groups SET group_name = $2, paused = $3, max_parallelism = $4 WHERE group_id = $1",
+        group_id,
+        definition.group_name,
+        definition.paused,
+        definition.max_parallelism
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    let names: Vec<String> = definition.jobs.iter().map(|j| j.name.clone()).collect();
+    let deleted = sqlx::query_scalar!(
+        "DELETE FROM cron_jobs WHERE group_id = $1 AND NOT (cron_job_name = ANY($2)) RETURNING cron_job_name",
+        group_id,
+        &names
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+
+    let now = Utc::now();
+    let mut ids: HashMap<&str, i32> = HashMap::new();
+    let (mut created, mut updated) = (Vec::new(), Vec::new());
+    for job in &definition.jobs {
+        let existing = sqlx::query_scalar!(
+            r#"
+            UPDATE cron_jobs
+            SET timings = COALESCE($3, timings), schedule = $4, s3_link = $5, dockerfile_path = $6,
+                max_retries = $7, retry_delay_seconds = $8, backoff_multiplier = $9,
+                timeout_seconds = $10, paused = $11
+            WHERE group_id = $1 AND cron_job_name = $2
+            RETURNING cron_job_id
+            "#,
+            group_id,
+            job.name,
+            job.timings,
+            job.schedule,
+            job.s3_link,
+            job.dockerfile_path,
+            job.max_retries,
+            job.retry_delay_seconds,
+            job.backoff_multiplier,
+            job.timeout_seconds,
+            job.paused
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+        let id = match existing {
+            Some(id) => {
+                updated.push(job.name.clone());
+                id
+            }
+            None => {
+                created.push(job.name.clone());
+                sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO cron_jobs (group_id, cron_job_name, timings, schedule, s3_link, dockerfile_path,
+                                           max_retries, retry_delay_seconds, backoff_multiplier,
+                                           timeout_seconds, paused)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+                    RETURNING cron_job_id
+                    "#,
+                    group_id,
+                    job.name,
+                    job.timings.unwrap_or(now),
+                    job.schedule,
+                    job.s3_link,
+                    job.dockerfile_path,
+                    job.max_retries,
+                    job.retry_delay_seconds,
+                    job.backoff_multiplier,
+                    job.timeout_seconds,
+                    job.paused
+                )
+                .fetch_one(&mut **tx)
+                .await?
+            }
+        };
+        ids.insert(job.name.as_str(), id);
+    }
+
+    // Edges are replaced wholesale now that every job has an id
+    let all_ids: Vec<i32> = ids.values().copied().collect();
+    sqlx::query!("DELETE FROM cron_job_dependencies WHERE cron_job_id = ANY($1)", &all_ids)
+        .execute(&mut **tx)
+        .await?;
+    for job in &definition.jobs {
+        let id = ids[job.name.as_str()];
+        let children: Vec<i32> = job.children.iter().map(|c| ids[c.as_str()]).collect();
+        sqlx::query!("UPDATE cron_jobs SET children = $2 WHERE cron_job_id = $1", id, &children)
+            .execute(&mut **tx)
+            .await?;
+        for dependency in &job.dependencies {
+            sqlx::query!(
+                "INSERT INTO cron_job_dependencies (cron_job_id, parent_id, epoch) VALUES ($1, $2, $3)",
+                id,
+                ids[dependency.job.as_str()],
+                dependency.epoch
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+
+    Ok(ImportSummary {
+        group_id,
+        dry_run: false,
+        created,
+        updated,
+        deleted,
+    })
+}
+
+/// Validate, apply and commit (or roll back, for a dry run) in one transaction.
+///
+/// An existing group is refused with 409 while a run of it is in progress.
+async fn import_definition(
+    state: &AppState,
+    group_id: Option<Uuid>,
+    headers: &HeaderMap,
+    body: &str,
+    dry_run: bool,
+) -> Result<ImportSummary, (StatusCode, String)> {
+    let definition = parse_definition(headers, body)?;
+    validate_definition(&definition).map_err(|errors| (StatusCode::UNPROCESSABLE_ENTITY, errors.join("\n")))?;
+
+    let internal = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let mut tx = state.pool.begin().await.map_err(internal)?;
+    let group_id = match group_id {
+        Some(group_id) => {
+            // Lock the group so concurrent imports apply one after the other; a run
+            // holds a share lock on it until registered, so none can start unseen
+            sqlx::query_scalar!("SELECT group_id FROM job_groups WHERE group_id = $1 FOR UPDATE", group_id)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(internal)?
+                .ok_or((StatusCode::NOT_FOUND, format!("group {} not found", group_id)))?;
+            if state.runs.in_progress(group_id) {
+                return Err((
+                    StatusCode::CONFLICT,
+                    format!("group {} has a run in progress; import again once it finishes", group_id),
+                ));
+            }
+            group_id
+        }
+        None => sqlx::query_scalar!(
+            "INSERT INTO job_groups (group_name) VALUES ($1) RETURNING group_id",
+            definition.group_name
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(internal)?,
+    };
+
+    let mut summary = apply_definition(&mut tx, group_id, &definition).await.map_err(internal)?;
+    if dry_run {
+        tx.rollback().await.map_err(internal)?;
+        summary.dry_run = true;
+    } else {
+        tx.commit().await.map_err(internal)?;
+        tracing::info!(%group_id, created = summary.created.len(), updated = summary.updated.len(),
+            deleted = summary.deleted.len(), "group definition imported");
+    }
+    Ok(summary)
+}
+
+/// Handler to create a new group from a definition (JSON, or YAML with a yaml content type).
+pub async fn create_group_from_definition(
+    State(state): State<AppState>,
+    Query(query): Query<ImportQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<(StatusCode, Json<ImportSummary>), (StatusCode, String)> {
+    let summary = import_definition(&state, None, &headers, &body, query.dry_run.unwrap_or(false)).await?;
+    Ok((StatusCode::CREATED, Json(summary)))
+}
+
+/// Handler to reconcile an existing group with a definition.
+///
+/// Refuses with 409 while a run of the group is in progress.
+pub async fn import_group_definition(
+    State(state): State<AppState>,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<ImportQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Json<ImportSummary>, (StatusCode, String)> {
+    import_definition(&state, Some(group_id), &headers, &body, query.dry_run.unwrap_or(false))
+        .await
+        .map(Json)
+}
+
+/// Handler to export a group in the format accepted by the import handlers.
+pub async fn export_group_definition(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let format = query.format.as_deref().unwrap_or("json");
+    if !matches!(format, "json" | "yaml") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("unknown format '{}': expected json or yaml", format),
+        ));
+    }
+
+    let group = sqlx::query!(
+        "SELECT group_name, paused, max_parallelism FROM job_groups WHERE group_id = $1",
+        group_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, format!("group {} not found", group_id)))?;
+    let (mut jobs, deps) = load_group_graph(&pool, group_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    jobs.sort_by_key(|j| j.cron_job_id);
+
+    let names: HashMap<i32, String> = jobs.iter().map(|j| (j.cron_job_id, j.cron_job_name.clone())).collect();
+    let ids: HashSet<i32> = names.keys().copied().collect();
+    let definition = GroupDefinition {
+        group_name: group.group_name,
+        paused: group.paused,
+        max_parallelism: group.max_parallelism,
+        jobs: jobs
+            .into_iter()
+            .map(|job| {
+                let mut dependencies: Vec<&_> = deps
+                    .iter()
+                    .filter(|d| d.cron_job_id == job.cron_job_id && ids.contains(&d.parent_id))
+                    .collect();
+                dependencies.sort_by_key(|d| d.parent_id);
+                JobDefinition {
+                    children: job
+                        .children
+                        .iter()
+                        .flatten()
+                        .filter_map(|c| names.get(c).cloned())
+                        .collect(),
+                    dependencies: dependencies
+                        .into_iter()
+                        .map(|d| DependencyDefinition {
+                            job: names[&d.parent_id].clone(),
+                            epoch: d.epoch,
+                        })
+                        .collect(),
+                    name: job.cron_job_name,
+                    timings: Some(job.timings),
+                    schedule: job.schedule,
+                    s3_link: job.s3_link,
+                    dockerfile_path: job.dockerfile_path,
+                    max_retries: job.max_retries,
+                    retry_delay_seconds: job.retry_delay_seconds,
+                    backoff_multiplier: job.backoff_multiplier,
+                    timeout_seconds: job.timeout_seconds,
+                    paused: job.paused,
+                }
+            })
+            .collect(),
+    };
+
+    Ok(match format {
+        "yaml" => {
+            let body = serde_yaml_ng::to_string(&definition)
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+            ([(header::CONTENT_TYPE, "application/yaml")], body).into_response()
+        }
+        _ => Json(definition).into_response(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(json: &str) -> GroupDefinition {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn errors(json: &str) -> Vec<String> {
+        validate_definition(&definition(json)).unwrap_err()
+    }
+
+    #[test]
+    fn accepts_edges_to_jobs_listed_later() {
+        let doc = definition(
+            r#"{"group_name": "etl", "max_parallelism": 2, "jobs": [
+                {"name": "extract", "children": ["transform"], "schedule": "0 * * * *"},
+                {"name": "load", "dependencies": [{"job": "transform"}, {"job": "load_report", "epoch": 1}]},
+                {"name": "transform"},
+                {"name": "load_report", "dependencies": [{"job": "load"}]}
+            ]}"#,
+        );
+        assert_eq!(validate_definition(&doc), Ok(()));
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let errors = errors(
+            r#"{"group_name": " ", "max_parallelism": 0, "jobs": [
+                {"name": "a", "children": ["missing"], "max_retries": -1},
+                {"name": "a"},
+                {"name": "b", "dependencies": [{"job": "nowhere"}]}
+            ]}"#,
+        );
+        assert_eq!(errors.len(), 6, "{:?}", errors);
+        assert!(errors.contains(&"group_name must not be empty".to_string()));
+        assert!(errors.contains(&"job 'a' is defined more than once".to_string()));
+        assert!(errors.contains(&"job 'a': max_retries must not be negative".to_string()));
+        assert!(errors.contains(&"job 'a': unknown child 'missing'".to_string()));
+        assert!(errors.contains(&"job 'b': unknown dependency 'nowhere'".to_string()));
+    }
+
+    #[test]
+    fn rejects_self_references() {
+        let errors = errors(
+            r#"{"group_name": "g", "jobs": [
+                {"name": "a", "children": ["a"]},
+                {"name": "b", "dependencies": [{"job": "b", "epoch": 1}]}
+            ]}"#,
+        );
+        assert_eq!(errors, ["job 'a' lists itself as a child", "job 'b' depends on itself"]);
+    }
+
+    #[test]
+    fn rejects_repeated_dependencies_on_one_parent() {
+        let errors = errors(
+            r#"{"group_name": "g", "jobs": [
+                {"name": "a"},
+                {"name": "b", "dependencies": [{"job": "a"}, {"job": "a", "epoch": 1}]}
+            ]}"#,
+        );
+        assert_eq!(errors, ["job 'b': dependency 'a' is listed more than once"]);
+    }
+
+    #[test]
+    fn rejects_same_run_cycles_but_not_epoch_dependencies() {
+        let errors = errors(
+            r#"{"group_name": "g", "jobs": [
+                {"name": "a", "children": ["b"], "dependencies": [{"job": "c"}]},
+                {"name": "b"},
+                {"name": "c", "dependencies": [{"job": "b"}]}
+            ]}"#,
+        );
+        assert_eq!(errors, ["jobs form a cycle: a -> b -> c -> a"]);
+
+        let doc = definition(
+            r#"{"group_name": "g", "jobs": [
+                {"name": "a", "children": ["b"]},
+                {"name": "b"},
+                {"name": "c", "dependencies": [{"job": "a", "epoch": 1}]}
+            ]}"#,
+        );
+        assert_eq!(validate_definition(&doc), Ok(()));
+    }
+
+    #[test]
+    fn yaml_and_json_parse_to_the_same_definition() {
+        let yaml: GroupDefinition = serde_yaml_ng::from_str(
+            "group_name: g\njobs:\n  - name: a\n    children: [b]\n  - name: b\n    dependencies:\n      - job: a\n        epoch: 1\n",
+        )
+        .unwrap();
+        let json = definition(
+            r#"{"group_name": "g", "jobs": [
+                {"name": "a", "children": ["b"]},
+                {"name": "b", "dependencies": [{"job": "a", "epoch": 1}]}
+            ]}"#,
+        );
+        assert_eq!(serde_json::to_value(&yaml).unwrap(), serde_json::to_value(&json).unwrap());
+        assert!(serde_json::from_str::<GroupDefinition>(r#"{"group_name": "g", "job": []}"#).is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::stream::{self, Stream};
+use serde_json::json;
+use sqlx::PgPool;
+use sqlx::postgres::PgListener;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+/// Postgres channel the `job_status` and `group_runs` triggers publish to.
+const CHANNEL: &str = "group_events";
+
+/// Events buffered per subscriber; one that falls further behind gets a new snapshot.
+const BUFFER: usize = 1024;
+
+/// Wait before listening again after the listener connection failed.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A status change published by the database, with the JSON sent to clients.
+#[derive(Clone, Debug)]
+pub struct StatusEvent {
+    pub kind: String, // 'job_status' or 'run_status'
+    pub group_id: Uuid,
+    pub payload: String,
+}
+
+/// Fan-out of status changes to every open event stream.
+#[derive(Clone)]
+pub struct StatusEvents(broadcast::Sender<StatusEvent>);
+
+impl StatusEvents {
+    pub fn new() -> Self {
+        Self(broadcast::channel(BUFFER).0)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StatusEvent> {
+        self.0.subscribe()
+    }
+}
+
+impl Default for StatusEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Long-running task relaying status notifications from Postgres to `events`.
+///
+/// Going through the database means every status write is seen, whichever code
+/// path made it. Changes made while the listener is reconnecting are not replayed.
+pub async fn relay_status_events(pool: PgPool, events: StatusEvents) {
+    let mut listener = loop {
+        match PgListener::connect_with(&pool).await {
+            Ok(mut listener) => match listener.listen(CHANNEL).await {
+                Ok(()) => break listener,
+                Err(e) => tracing::error!(error = %e, "failed to listen for status events"),
+            },
+            Err(e) => tracing::error!(error = %e, "failed to connect status event listener"),
+        }
+        tokio::time::sleep(RETRY_DELAY).await;
+    };
+
+    loop {
+        let notification = match listener.recv().await {
+            Ok(notification) => notification,
+            Err(e) => {
+                // The listener reconnects on the next recv
+                tracing::warn!(error = %e, "status event listener disconnected");
+                tokio::time::sleep(RETRY_DELAY).await;
+                continue;
+            }
+        };
+        let parsed: serde_json::Value = serde_json::from_str(notification.payload()).unwrap_or_default();
+        let (Some(kind), Some(group_id)) = (
+            parsed["kind"].as_str(),
+            parsed["group_id"].as_str().and_then(|id| id.parse().ok()),
+        ) else {
+            tracing::warn!(payload = notification.payload(), "ignoring malformed status event");
+            continue;
+        };
+        // Sending only fails when no stream is open
+        let _ = events.0.send(StatusEvent {
+            kind: kind.to_string(),
+            group_id,
+            payload: parsed.to_string(),
+        });
+    }
+}
+
+/// Every job of the group with its current status, plus the latest run.
+async fn group_snapshot(pool: &PgPool, group_id: Uuid) -> Result<String, sqlx::Error> {
+    let jobs = sqlx::query!(
+        r#"
+        SELECT j.cron_job_id, j.cron_job_name, s.status AS "status?", s.updated_at AS "updated_at?"
+        FROM cron_jobs j
+        LEFT JOIN job_status s ON s.cron_job_id = j.cron_job_id AND s.group_id = j.group_id
+        WHERE j.group_id = $1
+        ORDER BY j.cron_job_id
+        "#,
+        group_id
+    )
+    .fetch_all(pool)
+    .await?;
+    let run = sqlx::query!(
+        r#"
+        SELECT run_id, status, started_at, finished_at FROM group_runs
+        WHERE group_id = $1
+        ORDER BY started_at DESC
+        LIMIT 1
+        "#,
+        group_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let jobs: Vec<serde_json::Value> = jobs
+        .into_iter()
+        .map(|j| {
+            json!({
+                "job_id": j.cron_job_id,
+                "job_name": j.cron_job_name,
+                "status": j.status,
+                "updated_at": j.updated_at
+            })
+        })
+        .collect();
+    let run = run.map(|r| {
+        json!({
+            "run_id": r.run_id,
+            "status": r.status,
+            "started_at": r.started_at,
+            "finished_at": r.finished_at
+        })
+    });
+    Ok(json!({ "group_id": group_id, "jobs": jobs, "run": run }).to_string())
+}
+
+/// Handler streaming a group's status changes as server-sent events.
+///
+/// The stream opens with a `snapshot` event holding every job's status and the
+/// latest run, then sends a `job_status` or `run_status` event per change. A
+/// client that falls behind gets a fresh `snapshot` in place of what it missed.
+pub async fn stream_group_events(
+    State(state): State<AppState>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    // Subscribe before reading the snapshot so no change falls between the two
+    let receiver = state.events.subscribe();
+
+    let exists = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM job_groups WHERE group_id = $1)", group_id)
+        .fetch_one(&state.pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if exists != Some(true) {
+        return Err((StatusCode::NOT_FOUND, format!("group {} not found", group_id)));
+    }
+    let snapshot = group_snapshot(&state.pool, group_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let pool = state.pool.clone();
+    let events = stream::unfold((receiver, Some(snapshot)), move |(mut receiver, snapshot)| {
+        let pool = pool.clone();
+        async move {
+            if let Some(snapshot) = snapshot {
+                let event = Event::default().event("snapshot").data(snapshot);
+                return Some((Ok(event), (receiver, None)));
+            }
+            loop {
+                match receiver.recv().await {
+                    Ok(change) if change.group_id == group_id => {
+                        let event = Event::default().event(change.kind).data(change.payload);
+                        return Some((Ok(event), (receiver, None)));
+                    }
+                    Ok(_) => continue,
+                    // Ending the stream on failure lets the client reconnect for a new snapshot
+                    Err(RecvError::Lagged(_)) => {
+                        let snapshot = group_snapshot(&pool, group_id).await.ok()?;
+                        let event = Event::default().event("snapshot").data(snapshot);
+                        return Some((Ok(event), (receiver, None)));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
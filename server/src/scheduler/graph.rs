@@ -0,0 +1,282 @@
+use crate::scheduler::cron::load_group_graph;
+use crate::scheduler::dag::build_edges;
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use uuid::Uuid;
+
+/// Query parameters for rendering a group's graph.
+#[derive(Debug, Deserialize)]
+pub struct GraphQuery {
+    pub format: Option<String>, // 'json' (default), 'dot' or 'mermaid'
+    pub status: Option<bool>,   // include and color by each job's latest job_status
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphNode {
+    pub job_id: i32,
+    pub name: String,
+    pub paused: bool,
+    pub status: Option<String>, // only with `status=true`, None if the job never ran
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphEdge {
+    pub from: i32,  // parent job_id
+    pub to: i32,    // child job_id
+    pub epoch: i32, // 0: same run; N: parent must have completed in its Nth latest run
+}
+
+#[derive(Debug, Serialize)]
+pub struct GroupGraph {
+    pub group_id: Uuid,
+    pub group_name: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+/// Fill color of a job node in rendered graphs.
+fn status_color(status: Option<&str>) -> &'static str {
+    match status {
+        Some("completed") => "#c6f6d5",
+        Some("running") => "#fefcbf",
+        Some("queued" | "retrying") => "#bee3f8",
+        Some("failed" | "timed_out") => "#fed7d7",
+        Some("skipped" | "cancelled") => "#e2e8f0",
+        _ => "#ffffff",
+    }
+}
+
+/// Node label: the job name, plus its status when known.
+fn node_label(node: &GraphNode) -> String {
+    let mut label = node.name.clone();
+    if let Some(status) = &node.status {
+        label.push_str(&format!("\n{}", status));
+    }
+    if node.paused {
+        label.push_str("\n(paused)");
+    }
+    label
+}
+
+/// Render the graph in Graphviz DOT.
+pub fn render_dot(graph: &GroupGraph) -> String {
+    fn quote(s: &str) -> String {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph {} {{", quote(&graph.group_name));
+    let _ = writeln!(out, "    rankdir=LR;");
+    let _ = writeln!(out, "    node [shape=box, style=\"rounded,filled\", fillcolor=\"#ffffff\"];");
+    for node in &graph.nodes {
+        let _ = writeln!(
+            out,
+            "    n{} [label={}, fillcolor=\"{}\"];",
+            node.job_id,
+            quote(&node_label(node)),
+            status_color(node.status.as_deref())
+        );
+    }
+    for edge in &graph.edges {
+        if edge.epoch == 0 {
+            let _ = writeln!(out, "    n{} -> n{};", edge.from, edge.to);
+        } else {
+            let _ = writeln!(
+                out,
+                "    n{} -> n{} [style=dashed, label=\"epoch {}\"];",
+                edge.from, edge.to, edge.epoch
+            );
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Render the graph as a Mermaid flowchart.
+pub fn render_mermaid(graph: &GroupGraph) -> String {
+    fn quote(s: &str) -> String {
+        format!("\"{}\"", s.replace('"', "#quot;").replace('\n', "<br/>"))
+    }
+
+    let mut out = String::from("flowchart LR\n");
+    for node in &graph.nodes {
+        let _ = writeln!(out, "    n{}[{}]", node.job_id, quote(&node_label(node)));
+    }
+    for edge in &graph.edges {
+        if edge.epoch == 0 {
+            let _ = writeln!(out, "    n{} --> n{}", edge.from, edge.to);
+        } else {
+            let _ = writeln!(out, "    n{} -.->|epoch {}| n{}", edge.from, edge.epoch, edge.to);
+        }
+    }
+    for node in &graph.nodes {
+        if node.status.is_some() {
+            let _ = writeln!(
+                out,
+                "    style n{} fill:{}",
+                node.job_id,
+                status_color(node.status.as_deref())
+            );
+        }
+    }
+    out
+}
+
+/// Handler to render a group's job graph as JSON, Graphviz DOT or Mermaid.
+///
+/// Solid edges order jobs within a run (`children` and epoch 0 dependencies);
+/// dashed edges are dependencies on earlier runs.
+pub async fn get_group_graph(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<GraphQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let format = query.format.as_deref().unwrap_or("json");
+    if !matches!(format, "json" | "dot" | "mermaid") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("unknown format '{}': expected json, dot or mermaid", format),
+        ));
+    }
+
+    let group_name = sqlx::query_scalar!("SELECT group_name FROM job_groups WHERE group_id = $1", group_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, format!("group {} not found", group_id)))?;
+    let (mut jobs, deps) = load_group_graph(&pool, group_id)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    jobs.sort_by_key(|j| j.cron_job_id);
+
+    let statuses: HashMap<i32, String> = if query.status.unwrap_or(false) {
+        sqlx::query!("SELECT cron_job_id, status FROM job_status WHERE group_id = $1", group_id)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .into_iter()
+            .map(|r| (r.cron_job_id, r.status))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let ids: HashSet<i32> = jobs.iter().map(|j| j.cron_job_id).collect();
+    let mut edges: Vec<GraphEdge> = build_edges(&jobs, &deps)
+        .into_iter()
+        .flat_map(|(from, children)| children.into_iter().map(move |to| GraphEdge { from, to, epoch: 0 }))
+        .chain(deps.iter().filter(|d| d.epoch > 0).map(|d| GraphEdge {
+            from: d.parent_id,
+            to: d.cron_job_id,
+            epoch: d.epoch,
+        }))
+        .filter(|e| ids.contains(&e.from) && ids.contains(&e.to))
+        .collect();
+    edges.sort_by_key(|e| (e.from, e.to, e.epoch));
+
+    let graph = GroupGraph {
+        group_id,
+        group_name,
+        nodes: jobs
+            .into_iter()
+            .map(|j| GraphNode {
+                status: statuses.get(&j.cron_job_id).cloned(),
+                job_id: j.cron_job_id,
+                name: j.cron_job_name,
+                paused: j.paused,
+            })
+            .collect(),
+        edges,
+    };
+
+    Ok(match format {
+        "dot" => ([(header::CONTENT_TYPE, "text/vnd.graphviz; charset=utf-8")], render_dot(&graph)).into_response(),
+        "mermaid" => ([(header::CONTENT_TYPE, "text/plain; charset=utf-8")], render_mermaid(&graph)).into_response(),
+        _ => Json(graph).into_response(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(job_id: i32, name: &str, status: Option<&str>) -> GraphNode {
+        GraphNode {
+            job_id,
+            name: name.to_string(),
+            paused: false,
+            status: status.map(String::from),
+        }
+    }
+
+    /// extract -> load in the same run, and load -> extract one run back.
+    fn graph(nodes: Vec<GraphNode>) -> GroupGraph {
+        GroupGraph {
+            group_id: Uuid::nil(),
+            group_name: "etl".to_string(),
+            nodes,
+            edges: vec![
+                GraphEdge { from: 1, to: 2, epoch: 0 },
+                GraphEdge { from: 2, to: 1, epoch: 1 },
+            ],
+        }
+    }
+
+    #[test]
+    fn epoch_edges_are_dashed() {
+        let graph = graph(vec![node(1, "extract", None), node(2, "load", None)]);
+
+        let dot = render_dot(&graph);
+        assert!(dot.contains("    n1 -> n2;\n"), "{}", dot);
+        assert!(dot.contains("    n2 -> n1 [style=dashed, label=\"epoch 1\"];\n"), "{}", dot);
+
+        let mermaid = render_mermaid(&graph);
+        assert!(mermaid.contains("    n1 --> n2\n"), "{}", mermaid);
+        assert!(mermaid.contains("    n2 -.->|epoch 1| n1\n"), "{}", mermaid);
+    }
+
+    #[test]
+    fn nodes_are_filled_by_status() {
+        let graph = graph(vec![node(1, "extract", Some("completed")), node(2, "load", Some("timed_out"))]);
+
+        let dot = render_dot(&graph);
+        assert!(dot.contains("    n1 [label=\"extract\\ncompleted\", fillcolor=\"#c6f6d5\"];\n"), "{}", dot);
+        assert!(dot.contains("    n2 [label=\"load\\ntimed_out\", fillcolor=\"#fed7d7\"];\n"), "{}", dot);
+
+        let mermaid = render_mermaid(&graph);
+        assert!(mermaid.contains("    style n1 fill:#c6f6d5\n"), "{}", mermaid);
+        assert!(mermaid.contains("    style n2 fill:#fed7d7\n"), "{}", mermaid);
+    }
+
+    #[test]
+    fn jobs_without_status_keep_the_default_fill() {
+        let graph = graph(vec![node(1, "extract", None), node(2, "load", Some("running"))]);
+
+        let dot = render_dot(&graph);
+        assert!(dot.contains("    n1 [label=\"extract\", fillcolor=\"#ffffff\"];\n"), "{}", dot);
+
+        let mermaid = render_mermaid(&graph);
+        assert!(!mermaid.contains("style n1 "), "{}", mermaid);
+        assert!(mermaid.contains("    style n2 fill:#fefcbf\n"), "{}", mermaid);
+    }
+
+    #[test]
+    fn names_are_escaped() {
+        let graph = graph(vec![node(1, "say \"hi\"\\now", None), node(2, "two\nlines", None)]);
+
+        let dot = render_dot(&graph);
+        assert!(dot.contains(r#"    n1 [label="say \"hi\"\\now", "#), "{}", dot);
+        assert!(dot.contains(r#"    n2 [label="two\nlines", "#), "{}", dot);
+
+        let mermaid = render_mermaid(&graph);
+        assert!(mermaid.contains("    n1[\"say #quot;hi#quot;\\now\"]\n"), "{}", mermaid);
+        assert!(mermaid.contains("    n2[\"two<br/>lines\"]\n"), "{}", mermaid);
+    }
+}
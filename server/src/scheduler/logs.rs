@@ -0,0 +1,101 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+use serde::Deserialize;
+use serde_json::json;
+use sqlx::PgPool;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+/// Path of the log file for one attempt of a job within a run, one subdirectory
+/// of `log_dir` per run.
+pub fn log_path(log_dir: &std::path::Path, run_id: Uuid, job_id: i32, attempt: i32) -> PathBuf {
+    log_dir
+        .join(run_id.to_string())
+        .join(format!("{}_{}.log", job_id, attempt))
+}
+
+/// Create (or truncate) the log file for an attempt, creating its run directory.
+pub fn create_log_file(path: &std::path::Path) -> std::io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    File::create(path)
+}
+
+/// The last `max_bytes` of a log file, if it can be read.
+pub fn read_log_tail(path: &std::path::Path, max_bytes: u64) -> Option<String> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(max_bytes))).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Query parameters for reading a job log.
+#[derive(Debug, Deserialize)]
+pub struct LogQuery {
+    pub run_id: Option<Uuid>, // defaults to the latest attempt of the job
+    pub offset: Option<u64>,  // byte offset to start reading from
+    pub tail: Option<usize>,  // only return the last N lines
+}
+
+/// Handler to read the captured build and run output of a job attempt.
+///
+/// Returns `next_offset` so callers can poll with `offset` to follow a running job.
+pub async fn get_cron_job_logs(
+    State(pool): State<PgPool>,
+    Path((group_id, job_id)): Path<(Uuid, i32)>,
+    Query(query): Query<LogQuery>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let rec = sqlx::query!(
+        r#"
+        SELECT jr.job_run_id, jr.run_id, jr.status, jr.log_path
+        FROM job_runs jr
+        JOIN group_runs gr ON gr.run_id = jr.run_id
+        WHERE gr.group_id = $1 AND jr.cron_job_id = $2 AND ($3::uuid IS NULL OR jr.run_id = $3)
+        ORDER BY jr.started_at DESC, jr.job_run_id DESC
+        LIMIT 1
+        "#,
+        group_id,
+        job_id,
+        query.run_id
+    )
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .ok_or((StatusCode::NOT_FOUND, format!("no runs found for job {}", job_id)))?;
+
+    let path = rec
+        .log_path
+        .ok_or((StatusCode::NOT_FOUND, "no log recorded for this attempt".to_string()))?;
+    let mut file = File::open(&path).map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+
+    let offset = query.offset.unwrap_or(0);
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let next_offset = offset + bytes.len() as u64;
+
+    let mut log = String::from_utf8_lossy(&bytes).into_owned();
+    if let Some(n) = query.tail {
+        let lines: Vec<&str> = log.lines().collect();
+        log = lines[lines.len().saturating_sub(n)..].join("\n");
+    }
+
+    Ok(Json(json!({
+        "job_id": job_id,
+        "group_id": group_id,
+        "run_id": rec.run_id,
+        "job_run_id": rec.job_run_id,
+        "status": rec.status,
+        "log": log,
+        "next_offset": next_offset
+    })))
+}
@@ -0,0 +1,233 @@
+use crate::state::AppState;
+use axum::{
+    extract::{Json, Path, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+/// A single execution of a job group.
+#[derive(Debug, Serialize, FromRow)]
+pub struct GroupRun {
+    pub run_id: Uuid,
+    pub group_id: Uuid,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// A single job attempt within a group run.
+#[derive(Debug, Serialize, FromRow)]
+pub struct JobRun {
+    pub job_run_id: i32,
+    pub run_id: Uuid,
+    pub cron_job_id: i32,
+    pub attempt: i32,
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub log_path: Option<String>,
+    pub image_digest: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Response body for a group run together with its job attempts.
+#[derive(Debug, Serialize)]
+pub struct GroupRunDetail {
+    #[serde(flatten)]
+    pub run: GroupRun,
+    pub jobs: Vec<JobRun>,
+}
+
+/// Insert a new `running` group run and return its id.
+pub async fn start_group_run(pool: &PgPool, group_id: Uuid) -> Result<Uuid, sqlx::Error> {
+    let rec = sqlx::query!(
+        "INSERT INTO group_runs (group_id, status) VALUES ($1, 'running') RETURNING run_id",
+        group_id
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(rec.run_id)
+}
+
+/// Mark a group run as finished with its final status.
+pub async fn finish_group_run(pool: &PgPool, run_id: Uuid, status: &str) {
+    let _ = sqlx::query!(
+        "UPDATE group_runs SET status = $2, finished_at = NOW() WHERE run_id = $1",
+        run_id,
+        status
+    )
+    .execute(pool)
+    .await;
+}
+
+/// Insert a new `running` job attempt and return its id.
+pub async fn start_job_run(
+    pool: &PgPool,
+    run_id: Uuid,
+    job_id: i32,
+    attempt: i32,
+    log_path: &std::path::Path,
+) -> Option<i32> {
+    let log_path = log_path.to_string_lossy();
+    sqlx::query!(
+        "INSERT INTO job_runs (run_id, cron_job_id, attempt, status, log_path) VALUES ($1, $2, $3, 'running', $4) RETURNING job_run_id",
+        run_id,
+        job_id,
+        attempt,
+        log_path.as_ref()
+    )
+    .fetch_one(pool)
+    .await
+    .map(|r| r.job_run_id)
+    .ok()
+}
+
+/// Record the outcome of a job attempt.
+pub async fn finish_job_run(pool: &PgPool, job_run_id: Option<i32>, status: &str, exit_code: Option<i32>) {
+    let Some(job_run_id) = job_run_id else { return };
+    let _ = sqlx::query!(
+        "UPDATE job_runs SET status = $2, exit_code = $3, finished_at = NOW() WHERE job_run_id = $1",
+        job_run_id,
+        status,
+        exit_code
+    )
+    .execute(pool)
+    .await;
+}
+
+/// Record the digest of the image a job attempt runs.
+pub async fn set_job_run_image(pool: &PgPool, job_run_id: Option<i32>, digest: &str) {
+    let Some(job_run_id) = job_run_id else { return };
+    let _ = sqlx::query!(
+        "UPDATE job_runs SET image_digest = $2 WHERE job_run_id = $1",
+        job_run_id,
+        digest
+    )
+    .execute(pool)
+    .await;
+}
+
+/// The image digest `job_id` last ran with, if it differs from `digest` and no
+/// other job's latest attempt ran it too.
+pub async fn superseded_image_digest(pool: &PgPool, job_id: i32, digest: &str) -> Option<String> {
+    sqlx::query_scalar!(
+        r#"
+        SELECT prev.image_digest AS "image_digest!" FROM (
+            SELECT image_digest FROM job_runs
+            WHERE cron_job_id = $1 AND image_digest IS NOT NULL
+            ORDER BY job_run_id DESC
+            LIMIT 1
+        ) prev
+        WHERE prev.image_digest <> $2 AND NOT EXISTS (
+            SELECT 1 FROM cron_jobs j
+            WHERE j.cron_job_id <> $1 AND prev.image_digest = (
+                SELECT image_digest FROM job_runs r
+                WHERE r.cron_job_id = j.cron_job_id AND r.image_digest IS NOT NULL
+                ORDER BY r.job_run_id DESC
+                LIMIT 1
+            )
+        )
+        "#,
+        job_id,
+        digest
+    )
+    .fetch_optional(pool)
+    .await
+    .ok()
+    .flatten()
+}
+
+/// Whether `job_id` completed in the `epoch`-th most recent run of `group_id`
+/// that attempted it (`epoch` = 1 is the last such run).
+///
+/// Runs that never attempted the job, such as scheduled runs of other jobs in
+/// the group, are not counted.
+pub async fn completed_in_past_run(
+    pool: &PgPool,
+    group_id: Uuid,
+    job_id: i32,
+    epoch: i32,
+) -> Result<bool, sqlx::Error> {
+    let rec = sqlx::query!(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM job_runs
+            WHERE cron_job_id = $2 AND status = 'completed' AND run_id = (
+                SELECT gr.run_id FROM group_runs gr
+                WHERE gr.group_id = $1
+                  AND EXISTS (SELECT 1 FROM job_runs jr WHERE jr.run_id = gr.run_id AND jr.cron_job_id = $2)
+                ORDER BY gr.started_at DESC
+                OFFSET $3 LIMIT 1
+            )
+        ) AS "completed!"
+        "#,
+        group_id,
+        job_id,
+        (epoch - 1) as i64
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(rec.completed)
+}
+
+/// Handler to list the runs of a group, most recent first.
+pub async fn get_group_runs(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<Vec<GroupRun>>, (StatusCode, String)> {
+    let runs = sqlx::query_as::<_, GroupRun>(
+        "SELECT * FROM group_runs WHERE group_id = $1 ORDER BY started_at DESC",
+    )
+    .bind(group_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(runs))
+}
+
+/// Handler to fetch a single run with every job attempt it made.
+pub async fn get_group_run(
+    State(pool): State<PgPool>,
+    Path(run_id): Path<Uuid>,
+) -> Result<Json<GroupRunDetail>, (StatusCode, String)> {
+    let run = sqlx::query_as::<_, GroupRun>("SELECT * FROM group_runs WHERE run_id = $1")
+        .bind(run_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, format!("run {} not found", run_id)))?;
+
+    let jobs = sqlx::query_as::<_, JobRun>(
+        "SELECT * FROM job_runs WHERE run_id = $1 ORDER BY started_at, job_run_id",
+    )
+    .bind(run_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(GroupRunDetail { run, jobs }))
+}
+
+/// Handler to cancel every in-flight run of a group.
+///
+/// Running containers are killed, jobs that have not started are marked
+/// `cancelled`, and no further dependents are spawned.
+pub async fn cancel_group_runs(
+    State(state): State<AppState>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    let runs = state.runs.for_group(group_id);
+    if runs.is_empty() {
+        return Err((StatusCode::NOT_FOUND, format!("group {} has no run in progress", group_id)));
+    }
+    let mut cancelled = Vec::new();
+    for run in &runs {
+        let pending = run.cancel(&state.pool).await;
+        cancelled.push(json!({ "run_id": run.run_id, "never_started": pending }));
+    }
+    Ok(Json(json!({ "group_id": group_id, "cancelled": cancelled })))
+}
@@ -1,10 +1,40 @@
 use chrono::{DateTime, Utc};
 use std::collections::{HashMap};
-use std::fs;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
 use sqlx::PgPool;
-use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use flate2::read::GzDecoder;
+use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
+use shiplift::{BuildOptions, ContainerOptions, Docker, LogsOptions, RmContainerOptions};
+use tar::Archive;
+use tracing::Instrument;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
 use crate::scheduler::cron::CronJob;
+use crate::scheduler::logs::{create_log_file, log_path, read_log_tail};
+use crate::config::Config;
+use crate::metrics::Metrics;
+use crate::scheduler::slots::{JobSlot, JobSlots};
+use crate::scheduler::webhooks::{LOG_EXCERPT_BYTES, WebhookEvent, Webhooks};
+use crate::state::AppState;
+use crate::scheduler::runs::{finish_group_run, finish_job_run, set_job_run_image, start_job_run, superseded_image_digest};
+
+/// Dockerfile location used when a job does not set `dockerfile_path`.
+const DEFAULT_DOCKERFILE: &str = "Dockerfile";
+
+/// Upper bound on the backoff between two attempts of a job.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// Upper bound on the bytes an archived build context may unpack to.
+const MAX_BUILD_CONTEXT_BYTES: u64 = 1 << 30;
+
+/// Upper bound on the number of entries in an archived build context.
+const MAX_BUILD_CONTEXT_ENTRIES: usize = 10_000;
 // Helper function to build dependents map: parent_id -> Vec<child_id>
 fn build_dependents_map(dependencies: &HashMap<i32, Vec<i32>>) -> HashMap<i32, Vec<i32>> {
     let mut dependents: HashMap<i32, Vec<i32>> = HashMap::new();
@@ -16,6 +46,374 @@ fn build_dependents_map(dependencies: &HashMap<i32, Vec<i32>>) -> HashMap<i32, V
     dependents
 }
 
+/// Upsert the current status of a job in `job_status`.
+pub async fn set_job_status(pool: &PgPool, job_id: i32, group_id: Uuid, status: &str) {
+    let _ = sqlx::query!(
+        "INSERT INTO job_status (cron_job_id, group_id, status, updated_at) VALUES ($1,$2,$3,NOW()) ON CONFLICT (cron_job_id, group_id) DO UPDATE SET status=$3, updated_at=NOW()",
+        job_id,
+        group_id,
+        status
+    )
+    .execute(pool)
+    .await;
+}
+
+/// Why a job attempt finished without a container exit code.
+#[derive(Debug)]
+enum AttemptError {
+    Failed(String),
+    TimedOut(Duration),
+    Cancelled,
+}
+
+impl std::fmt::Display for AttemptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttemptError::Failed(reason) => write!(f, "{}", reason),
+            AttemptError::TimedOut(limit) => write!(f, "container killed after {:?} timeout", limit),
+            AttemptError::Cancelled => write!(f, "run was cancelled"),
+        }
+    }
+}
+
+impl From<String> for AttemptError {
+    fn from(reason: String) -> Self {
+        AttemptError::Failed(reason)
+    }
+}
+
+/// Write a job's downloaded build input into `dir` as its build context.
+///
+/// Tar and gzipped tar archives are unpacked in full; anything else is taken to
+/// be a lone Dockerfile and written to `dockerfile`.
+fn unpack_build_context(bytes: &[u8], dir: &Path, dockerfile: &str) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("creating build context failed: {}", e))?;
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        unpack_archive(GzDecoder::new(bytes), dir)?;
+    } else if bytes.get(257..262) == Some(b"ustar") {
+        unpack_archive(bytes, dir)?;
+    } else {
+        return fs::write(dir.join(dockerfile), bytes).map_err(|e| format!("writing Dockerfile failed: {}", e));
+    }
+    if !dir.join(dockerfile).is_file() {
+        return Err(format!("build context has no Dockerfile at '{}'", dockerfile));
+    }
+    Ok(())
+}
+
+/// Unpack a tar archive into `dir`, rejecting links, entries that escape it and
+/// archives larger than `MAX_BUILD_CONTEXT_BYTES` or `MAX_BUILD_CONTEXT_ENTRIES`.
+fn unpack_archive(reader: impl Read, dir: &Path) -> Result<(), String> {
+    let mut archive = Archive::new(reader);
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("reading build context failed: {}", e))?;
+    let mut unpacked_bytes = 0u64;
+    for (index, entry) in entries.enumerate() {
+        if index >= MAX_BUILD_CONTEXT_ENTRIES {
+            return Err(format!("build context has more than {} entries", MAX_BUILD_CONTEXT_ENTRIES));
+        }
+        let mut entry = entry.map_err(|e| format!("reading build context failed: {}", e))?;
+        let kind = entry.header().entry_type();
+        let name = entry.path().map(|p| p.display().to_string()).unwrap_or_default();
+        if kind.is_symlink() || kind.is_hard_link() {
+            return Err(format!("build context may not contain links: '{}'", name));
+        }
+        if !(kind.is_file() || kind.is_dir()) {
+            continue;
+        }
+        // Checked against the header before anything is written
+        unpacked_bytes = unpacked_bytes.saturating_add(entry.size());
+        if unpacked_bytes > MAX_BUILD_CONTEXT_BYTES {
+            return Err(format!("build context unpacks to more than {} bytes", MAX_BUILD_CONTEXT_BYTES));
+        }
+        let unpacked = entry
+            .unpack_in(dir)
+            .map_err(|e| format!("unpacking '{}' failed: {}", name, e))?;
+        if !unpacked {
+            return Err(format!("build context entry '{}' is outside the archive root", name));
+        }
+    }
+    Ok(())
+}
+
+/// Remove an attempt's build context, and its run directory once that is empty.
+fn remove_build_context(dir: &Path) {
+    let _ = fs::remove_dir_all(dir);
+    if let Some(run_dir) = dir.parent() {
+        let _ = fs::remove_dir(run_dir);
+    }
+}
+
+/// Build `image_name` from `dockerfile` in `context`, streaming build output to `log`.
+///
+/// `nocache` bypasses Docker's layer cache as well.
+async fn build_image(
+    docker: &Docker,
+    context: &Path,
+    dockerfile: &str,
+    image_name: &str,
+    nocache: bool,
+    log: &mut File,
+) -> Result<(), String> {
+    let options = BuildOptions::builder(context.to_string_lossy())
+        .dockerfile(dockerfile)
+        .tag(image_name)
+        .nocache(nocache)
+        .build();
+    let mut output = docker.images().build(&options);
+    while let Some(chunk) = output.next().await {
+        let chunk = chunk.map_err(|e| format!("docker build failed: {}", e))?;
+        if let Some(line) = chunk.get("stream").and_then(|v| v.as_str()) {
+            let _ = log.write_all(line.as_bytes());
+        }
+        if let Some(error) = chunk.get("error").and_then(|v| v.as_str()) {
+            let _ = writeln!(log, "{}", error);
+            return Err(format!("docker build failed: {}", error));
+        }
+    }
+    Ok(())
+}
+
+/// Force-remove a container by id, ignoring containers that do not exist.
+async fn remove_container(docker: &Docker, container: &str) {
+    let _ = docker
+        .containers()
+        .get(container)
+        .remove(RmContainerOptions::builder().force(true).build())
+        .await;
+}
+
+/// Remove an image, unless a container still uses it.
+async fn remove_image(docker: &Docker, image_name: &str) {
+    if let Err(e) = docker.images().get(image_name).delete().await {
+        tracing::debug!(image = image_name, error = %e, "could not remove image");
+    }
+}
+
+/// Content-addressed tag of the image built from a build input with this digest.
+fn image_name(digest: &str) -> String {
+    format!("cron_job_image:{}", digest)
+}
+
+/// SHA-256 of a job's build input, covering the Dockerfile it selects as well
+/// as the downloaded bytes.
+fn build_input_digest(bytes: &[u8], dockerfile: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(dockerfile.as_bytes());
+    hasher.update([0]);
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Whether `image_name` exists locally.
+///
+/// The inspect response is not needed and its shape varies across Docker
+/// versions, so a body that fails to parse still counts as the image existing.
+async fn image_exists(docker: &Docker, image_name: &str) -> bool {
+    matches!(
+        docker.images().get(image_name).inspect().await,
+        Ok(_) | Err(shiplift::Error::SerdeJsonError(_))
+    )
+}
+
+/// Download a job's build input and make sure an image built from it exists.
+///
+/// Images are tagged by the digest of their build input, so an unchanged input
+/// reuses the existing image unless the run asked for a rebuild. Once a changed
+/// input is built, the image the job ran before is removed. Returns the digest.
+async fn prepare_image(
+    docker: &Docker,
+    job: &CronJob,
+    run: &EpochState,
+    pool: &PgPool,
+    log: &mut File,
+) -> Result<String, AttemptError> {
+    let build_dir = run
+        .config
+        .build_dir()
+        .join(run.run_id.to_string())
+        .join(job.cron_job_id.to_string());
+    let dockerfile = job.dockerfile_path.clone().unwrap_or_else(|| DEFAULT_DOCKERFILE.to_string());
+    let s3_link = job.s3_link.clone().unwrap_or_default();
+
+    let bytes = reqwest::Client::builder()
+        .timeout(run.config.download_timeout())
+        .build()
+        .map_err(|e| format!("download failed: {}", e))?
+        .get(&s3_link)
+        .send()
+        .await
+        .map_err(|e| format!("download failed: {}", e))?
+        .bytes()
+        .await
+        .map_err(|e| format!("download failed: {}", e))?;
+    let digest = build_input_digest(&bytes, &dockerfile);
+    let image_name = image_name(&digest);
+    if !run.rebuild && image_exists(docker, &image_name).await {
+        let _ = writeln!(log, "=== using cached image {} ===", image_name);
+        return Ok(digest);
+    }
+
+    // Unpack the build input into this attempt's own build context
+    let unpacked = {
+        let (build_dir, dockerfile) = (build_dir.clone(), dockerfile.clone());
+        tokio::task::spawn_blocking(move || unpack_build_context(&bytes, &build_dir, &dockerfile))
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result)
+    };
+    if let Err(e) = unpacked {
+        remove_build_context(&build_dir);
+        return Err(e.into());
+    }
+
+    // build image
+    let _ = writeln!(log, "=== docker build {} ===", image_name);
+    let started = Instant::now();
+    let build = tokio::select! {
+        result = build_image(docker, &build_dir, &dockerfile, &image_name, run.rebuild, log) => result.map_err(AttemptError::from),
+        _ = run.cancel.cancelled() => Err(AttemptError::Cancelled),
+    };
+    remove_build_context(&build_dir);
+    run.metrics
+        .build_duration
+        .with_label_values(&[&run.group_id.to_string()])
+        .observe(started.elapsed().as_secs_f64());
+    build?;
+
+    if let Some(previous) = superseded_image_digest(pool, job.cron_job_id, &digest).await {
+        remove_image(docker, &self::image_name(&previous)).await;
+    }
+    Ok(digest)
+}
+
+/// Build, if needed, and run a job's image once, writing all output to `log`.
+///
+/// Returns the container's exit code, or an error if the job never got as far
+/// as running a container, the container outlived the job's `timeout_seconds`,
+/// or the run was cancelled.
+async fn run_job_attempt(
+    job: &CronJob,
+    attempt: i32,
+    job_run_id: Option<i32>,
+    mut log: File,
+    run: &EpochState,
+    pool: &PgPool,
+) -> Result<i32, AttemptError> {
+    // Unique per attempt, so overlapping runs of a group never share a container
+    let container_name = format!("cron_job_container_{}_{}_{}", run.run_id, job.cron_job_id, attempt);
+    let docker = &run.docker;
+
+    let digest = prepare_image(docker, job, run, pool, &mut log).await?;
+    set_job_run_image(pool, job_run_id, &digest).await;
+    let image_name = image_name(&digest);
+    let cancel = &run.cancel;
+
+    // run container
+    let _ = writeln!(log, "=== docker run {} ===", container_name);
+    let options = ContainerOptions::builder(&image_name).name(&container_name).build();
+    let created = docker
+        .containers()
+        .create(&options)
+        .await
+        .map_err(|e| format!("docker create failed: {}", e))?;
+    let container = docker.containers().get(&created.id);
+    if let Err(e) = container.start().await {
+        remove_container(docker, &created.id).await;
+        return Err(format!("docker start failed: {}", e).into());
+    }
+    let group_label = run.group_id.to_string();
+    let running = run.metrics.running_containers.with_label_values(&[&group_label]);
+    running.inc();
+    let started = Instant::now();
+
+    // Follow the container's output until it exits, stopping it on timeout or cancellation
+    let limit = job
+        .timeout_seconds
+        .map(|secs| secs as u64)
+        .or(run.config.executor.default_job_timeout_seconds)
+        .map(Duration::from_secs);
+    let deadline = async {
+        match limit {
+            Some(limit) => tokio::time::sleep(limit).await,
+            None => std::future::pending().await,
+        }
+    };
+    let follow = async {
+        let options = LogsOptions::builder().follow(true).stdout(true).stderr(true).build();
+        let mut output = container.logs(&options);
+        while let Some(Ok(chunk)) = output.next().await {
+            let _ = log.write_all(&Vec::<u8>::from(chunk));
+        }
+        container.wait().await
+    };
+    let result = tokio::select! {
+        exit = follow => exit
+            .map(|exit| exit.status_code as i32)
+            .map_err(|e| format!("docker wait failed: {}", e).into()),
+        _ = deadline => Err(AttemptError::TimedOut(limit.unwrap_or_default())),
+        _ = cancel.cancelled() => Err(AttemptError::Cancelled),
+    };
+    if let Err(stopped @ (AttemptError::TimedOut(_) | AttemptError::Cancelled)) = &result {
+        let _ = writeln!(log, "=== {}, killing {} ===", stopped, container_name);
+        let _ = container.kill(None).await;
+    }
+    remove_container(docker, &created.id).await;
+    running.dec();
+    run.metrics
+        .run_duration
+        .with_label_values(&[&group_label, attempt_status(&result)])
+        .observe(started.elapsed().as_secs_f64());
+    result
+}
+
+/// Status recorded for a finished attempt.
+fn attempt_status(result: &Result<i32, AttemptError>) -> &'static str {
+    match result {
+        Ok(0) => "completed",
+        Err(AttemptError::TimedOut(_)) => "timed_out",
+        Err(AttemptError::Cancelled) => "cancelled",
+        _ => "failed",
+    }
+}
+
+/// Delay before retrying after failed attempt number `attempt` (1-based):
+/// `retry_delay_seconds * backoff_multiplier^(attempt - 1)`, capped at `MAX_RETRY_DELAY`.
+fn retry_delay(job: &CronJob, attempt: i32) -> Duration {
+    let secs = job.retry_delay_seconds as f64 * job.backoff_multiplier.powi(attempt - 1);
+    Duration::try_from_secs_f64(secs)
+        .unwrap_or(MAX_RETRY_DELAY)
+        .min(MAX_RETRY_DELAY)
+}
+
+/// Append an executor error to an attempt's log so it shows up next to the job output.
+fn append_log(path: &std::path::Path, message: &str) {
+    if let Ok(mut log) = fs::OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(log, "=== error: {} ===", message);
+    }
+}
+
+/// Wait for a free job slot, holding the job as queued meanwhile.
+///
+/// Returns `None` if the run is cancelled first.
+async fn acquire_slot(job: &CronJob, epoch_state: &EpochState, pool: &PgPool) -> Option<JobSlot> {
+    let max_parallelism = epoch_state.max_parallelism;
+    if let Some(slot) = epoch_state.slots.try_acquire(job.group_id, max_parallelism) {
+        return Some(slot);
+    }
+    epoch_state.epochs.lock().unwrap().insert(job.cron_job_id, Epoch::Queued);
+    set_job_status(pool, job.cron_job_id, job.group_id, "queued").await;
+    let queued = epoch_state.metrics.queued_jobs.with_label_values(&[&job.group_id.to_string()]);
+    queued.inc();
+    let slot = tokio::select! {
+        slot = epoch_state.slots.acquire(job.group_id, max_parallelism) => Some(slot),
+        _ = epoch_state.cancel.cancelled() => None,
+    };
+    queued.dec();
+    slot
+}
+
 // Helper function to spawn a job and recursively trigger dependents
 fn spawn_job_and_dependents(
     job_id: i32,
@@ -25,150 +423,317 @@ fn spawn_job_and_dependents(
     epoch_state: EpochState,
     pool: PgPool,
 ) {
-    let job = jobs.get(&job_id).unwrap();
-    let dockerfile_path = format!("/tmp/dockerfile_{}", job_id);
-    let image_name = format!("cron_job_image_{}", job_id);
-    let container_name = format!("cron_job_container_{}", job_id);
-    let s3_link = job.s3_link.clone().unwrap_or_default();
-    let pool_clone = pool.clone();
-    let epochs_clone = epoch_state.epochs.clone();
-    let job_id_clone = job_id;
-    let group_id_clone = job.group_id.clone();
-    let dockerfile_path_clone = dockerfile_path.clone();
-    let image_name_clone = image_name.clone();
-    let container_name_clone = container_name.clone();
-    let jobs = jobs.clone();
-    let dependencies = dependencies.clone();
-    let dependents = dependents.clone();
-    let epoch_state_clone = epoch_state.clone();
+    let job = jobs.get(&job_id).unwrap().clone();
+    epoch_state.in_flight.fetch_add(1, Ordering::SeqCst);
+    // Job tasks outlive the request that started the run, so their span carries the run ids itself
+    let span = tracing::info_span!(
+        parent: None,
+        "job",
+        group_id = %job.group_id,
+        run_id = %epoch_state.run_id,
+        job_id
+    );
     tokio::spawn(async move {
+        if epoch_state.cancel.is_cancelled() {
+            epoch_state.release(&pool).await;
+            return;
+        }
+        let mut slot = acquire_slot(&job, &epoch_state, &pool).await;
+        if slot.is_none() {
+            epoch_state.epochs.lock().unwrap().insert(job_id, Epoch::Cancelled);
+            set_job_status(&pool, job_id, job.group_id, "cancelled").await;
+            epoch_state.release(&pool).await;
+            return;
+        }
+
         // Mark as running in memory and DB
         {
-            let mut epochs = epochs_clone.lock().unwrap();
-            epochs.insert(job_id_clone, Epoch::Running);
-        }
-        let _ = sqlx::query!(
-            "INSERT INTO job_status (cron_job_id, group_id, status, updated_at) VALUES ($1,$2,$3,NOW()) ON CONFLICT (cron_job_id, group_id) DO UPDATE SET status=$3, updated_at=NOW()",
-            job_id_clone,
-            group_id_clone,
-            "running"
-        )
-        .execute(&pool_clone)
-        .await;
+            let mut epochs = epoch_state.epochs.lock().unwrap();
+            epochs.insert(job_id, Epoch::Running);
+        }
+        set_job_status(&pool, job_id, job.group_id, "running").await;
+        tracing::info!("job started");
+
+        // Attempt the job, retrying failures according to its retry policy
+        let mut attempt = 1;
+        let status = loop {
+            let log_file = log_path(&epoch_state.config.log_dir(), epoch_state.run_id, job_id, attempt);
+            let job_run_id = start_job_run(&pool, epoch_state.run_id, job_id, attempt, &log_file).await;
 
-        // Download Dockerfile
-        let dockerfile_bytes = match reqwest::get(&s3_link).await {
-            Ok(resp) => match resp.bytes().await {
-                Ok(bytes) => bytes,
-                Err(_) => {
-                    {
-                    let mut epochs = epochs_clone.lock().unwrap();
-                    epochs.insert(job_id_clone, Epoch::Failed);}
-                    let _ = sqlx::query!(
-                        "INSERT INTO job_status (cron_job_id, group_id, status, updated_at) VALUES ($1,$2,'failed',NOW()) ON CONFLICT (cron_job_id, group_id) DO UPDATE SET status='failed', updated_at=NOW()",
-                        job_id_clone,
-                        group_id_clone
-                    )
-                    .execute(&pool_clone)
-                    .await;
-                    return;
+            let span = tracing::info_span!("attempt", attempt, job_run_id);
+            let started = Instant::now();
+            let result = match create_log_file(&log_file) {
+                Ok(log) => run_job_attempt(&job, attempt, job_run_id, log, &epoch_state, &pool).instrument(span.clone()).await,
+                Err(e) => Err(format!("creating log file failed: {}", e).into()),
+            };
+            let status = attempt_status(&result);
+            match &result {
+                Ok(0) => span.in_scope(|| tracing::info!(status, "attempt finished")),
+                Ok(exit_code) => span.in_scope(|| tracing::warn!(exit_code, status, "attempt failed")),
+                Err(e) => {
+                    span.in_scope(|| tracing::warn!(error = %e, status, "attempt failed"));
+                    append_log(&log_file, &e.to_string());
                 }
-            },
-            Err(_) => {
-                {let mut epochs = epochs_clone.lock().unwrap();
-                epochs.insert(job_id_clone, Epoch::Failed);}
-                let _ = sqlx::query!(
-                    "INSERT INTO job_status (cron_job_id, group_id, status, updated_at) VALUES ($1,$2,'failed',NOW()) ON CONFLICT (cron_job_id, group_id) DO UPDATE SET status='failed', updated_at=NOW()",
-                    job_id_clone,
-                    group_id_clone
-                )
-                .execute(&pool_clone)
-                .await;
-                return;
             }
-        };
-        if let Err(_) = fs::write(&dockerfile_path_clone, &dockerfile_bytes) {
-            {let mut epochs = epochs_clone.lock().unwrap();
-            epochs.insert(job_id_clone, Epoch::Failed);}
-            let _ = sqlx::query!(
-                "INSERT INTO job_status (cron_job_id, group_id, status, updated_at) VALUES ($1,$2,'failed',NOW()) ON CONFLICT (cron_job_id, group_id) DO UPDATE SET status='failed', updated_at=NOW()",
-                job_id_clone,
-                group_id_clone
-            )
-            .execute(&pool_clone)
-            .await;
-            return;
-        }
-        
-        // build image
-        let build = Command::new("docker")
-            .args(&["build", "-f", &dockerfile_path_clone, "-t", &image_name_clone, "/tmp"])
-            .status();
-
-        if build.is_err() || !build.as_ref().unwrap().success() {
-            {let mut epochs = epochs_clone.lock().unwrap();
-            epochs.insert(job_id_clone, Epoch::Failed);}
-            let _ = sqlx::query!(
-                "INSERT INTO job_status (cron_job_id, group_id, status, updated_at) VALUES ($1,$2,'failed',NOW()) ON CONFLICT (cron_job_id, group_id) DO UPDATE SET status='failed', updated_at=NOW()",
-                job_id_clone,
-                group_id_clone
-            )
-            .execute(&pool_clone)
-            .await;
-            return;
-        }
+            finish_job_run(&pool, job_run_id, status, result.as_ref().ok().copied()).await;
+            epoch_state
+                .metrics
+                .job_attempts
+                .with_label_values(&[&job.group_id.to_string(), status])
+                .inc();
+            if matches!(status, "failed" | "timed_out") {
+                epoch_state.webhooks.notify(WebhookEvent {
+                    event: if status == "timed_out" { "job_timed_out" } else { "job_failed" },
+                    group_id: job.group_id,
+                    run_id: epoch_state.run_id,
+                    job_id: Some(job_id),
+                    job_name: Some(job.cron_job_name.clone()),
+                    attempt: Some(attempt),
+                    will_retry: attempt <= job.max_retries,
+                    status: status.to_string(),
+                    duration_seconds: started.elapsed().as_secs_f64(),
+                    log_excerpt: read_log_tail(&log_file, LOG_EXCERPT_BYTES),
+                    timestamp: Utc::now(),
+                });
+            }
 
-        // run container
-        let run = Command::new("docker")
-            .args(&["run", "--rm", "--name", &container_name_clone, &image_name_clone])
-            .status();
+            if matches!(status, "completed" | "cancelled") || attempt > job.max_retries {
+                break status;
+            }
+            // No container runs during the backoff, so the slot goes to other jobs meanwhile
+            slot = None;
+            let delay = retry_delay(&job, attempt);
+            tracing::info!(?delay, "retrying job");
+            set_job_status(&pool, job_id, job.group_id, "retrying").await;
+            tokio::select! {
+                _ = tokio::time::sleep(delay) => {}
+                _ = epoch_state.cancel.cancelled() => break "cancelled",
+            }
+            slot = acquire_slot(&job, &epoch_state, &pool).await;
+            if slot.is_none() {
+                break "cancelled";
+            }
+            epoch_state.epochs.lock().unwrap().insert(job_id, Epoch::Running);
+            set_job_status(&pool, job_id, job.group_id, "running").await;
+            attempt += 1;
+        };
+        drop(slot);
 
-        let success = run.map(|s| s.success()).unwrap_or(false);
+        // A timed out job counts as failed for its dependents
         {
-            let mut epochs = epochs_clone.lock().unwrap();
-            epochs.insert(job_id_clone, if success { Epoch::Completed } else { Epoch::Failed });
-        }
-        let _ = sqlx::query!(
-            "INSERT INTO job_status (cron_job_id, group_id, status, updated_at) VALUES ($1,$2,$3,NOW()) ON CONFLICT (cron_job_id, group_id) DO UPDATE SET status=$3, updated_at=NOW()",
-            job_id_clone,
-            group_id_clone,
-            if success { "completed" } else { "failed" }
-        )
-        .execute(&pool_clone)
-        .await;
-        let _ = fs::remove_file(&dockerfile_path_clone);
+            let mut epochs = epoch_state.epochs.lock().unwrap();
+            epochs.insert(
+                job_id,
+                match status {
+                    "completed" => Epoch::Completed,
+                    "cancelled" => Epoch::Cancelled,
+                    _ => Epoch::Failed,
+                },
+            );
+        }
+        set_job_status(&pool, job_id, job.group_id, status).await;
+        tracing::info!(status, attempts = attempt, "job finished");
 
         // After completion, try to spawn dependents if they are now eligible
-        if let Some(children) = dependents.get(&job_id_clone) {
+        if let Some(children) = dependents.get(&job_id)
+            && !epoch_state.cancel.is_cancelled()
+        {
             for &child_id in children {
-                // Check if all dependencies are completed
-                let deps = dependencies.get(&child_id);
-                
-                let epochs = epoch_state_clone.epochs.lock().unwrap();
-                
-                let all_parents_done = deps.map(|ps| ps.iter().all(|p| epochs.get(p) == Some(&Epoch::Completed))).unwrap_or(true);
-                
-                
-                if all_parents_done {
-                    spawn_job_and_dependents(child_id, jobs.clone(), dependencies.clone(), dependents.clone(), epoch_state_clone.clone(), pool.clone());
+                if epoch_state.claim(child_id, dependencies.get(&child_id)) {
+                    spawn_job_and_dependents(child_id, jobs.clone(), dependencies.clone(), dependents.clone(), epoch_state.clone(), pool.clone());
                 }
-            
             }
         }
-    });
+
+        epoch_state.release(&pool).await;
+    }
+    .instrument(span));
 }
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Epoch {
     Pending,
+    Queued, // eligible, waiting for a free job slot
     Running,
     Completed,
     Failed,
+    Skipped,   // not eligible in this run; dependents never start
+    Cancelled, // stopped or never started because the run was cancelled
 }
 
+/// Group runs that are still executing, by run id.
 #[derive(Default, Clone)]
+pub struct ActiveRuns(Arc<Mutex<HashMap<Uuid, EpochState>>>);
+
+impl ActiveRuns {
+    /// Runs of `group_id` that have not finished yet.
+    pub fn for_group(&self, group_id: Uuid) -> Vec<EpochState> {
+        self.0
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|run| run.group_id == group_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Whether a run of `group_id` is in progress.
+    ///
+    /// Only runs of this process are live; `running` rows left by a restart are not.
+    pub fn in_progress(&self, group_id: Uuid) -> bool {
+        self.0.lock().unwrap().values().any(|run| run.group_id == group_id)
+    }
+
+    pub fn get(&self, run_id: Uuid) -> Option<EpochState> {
+        self.0.lock().unwrap().get(&run_id).cloned()
+    }
+}
+
+/// In-memory state shared by every job task of one group run.
+#[derive(Clone)]
 pub struct EpochState {
+    pub run_id: Uuid,
+    pub group_id: Uuid,
     pub epochs: Arc<Mutex<HashMap<i32, Epoch>>>, // job_id -> epoch
+    pub cancel: CancellationToken,
+    pub rebuild: bool, // build images even when a cached one matches the build input
+    pub max_parallelism: Option<usize>, // the group's limit on concurrently running jobs
+    in_flight: Arc<AtomicUsize>, // spawned tasks that have not finished yet
+    active_runs: ActiveRuns,
+    slots: JobSlots,
+    metrics: Metrics,
+    config: Arc<Config>,
+    docker: Docker,
+    webhooks: Webhooks,
+    started: Instant,
+}
+
+impl EpochState {
+    /// Create the state for a new run and register it as active.
+    pub fn new(
+        run_id: Uuid,
+        group_id: Uuid,
+        rebuild: bool,
+        max_parallelism: Option<usize>,
+        app: &AppState,
+    ) -> Self {
+        let state = Self {
+            run_id,
+            group_id,
+            epochs: Arc::default(),
+            cancel: CancellationToken::new(),
+            rebuild,
+            max_parallelism,
+            in_flight: Arc::default(),
+            active_runs: app.runs.clone(),
+            slots: app.slots.clone(),
+            metrics: app.metrics.clone(),
+            config: app.config.clone(),
+            docker: app.docker.clone(),
+            webhooks: app.webhooks.clone(),
+            started: Instant::now(),
+        };
+        app.runs.0.lock().unwrap().insert(run_id, state.clone());
+        state
+    }
+
+    /// Cancel the run: running containers are killed by their tasks, and jobs
+    /// that have not started yet are marked cancelled and never spawned.
+    ///
+    /// Returns the ids of the jobs that were still pending or queued.
+    pub async fn cancel(&self, pool: &PgPool) -> Vec<i32> {
+        self.cancel.cancel();
+        let pending: Vec<i32> = {
+            let mut epochs = self.epochs.lock().unwrap();
+            epochs
+                .iter_mut()
+                .filter(|(_, e)| matches!(e, Epoch::Pending | Epoch::Queued))
+                .map(|(&id, e)| {
+                    *e = Epoch::Cancelled;
+                    id
+                })
+                .collect()
+        };
+        for &job_id in &pending {
+            set_job_status(pool, job_id, self.group_id, "cancelled").await;
+        }
+        pending
+    }
+
+    /// Exclude a job from this run before it is started.
+    pub fn skip(&self, job_id: i32) {
+        self.epochs.lock().unwrap().insert(job_id, Epoch::Skipped);
+    }
+
+    /// Take a pending job for spawning once all of its same-run `parents` completed.
+    ///
+    /// The job is marked queued under the same lock, so of several parents
+    /// finishing at once only one spawns it. Returns whether the caller should.
+    fn claim(&self, job_id: i32, parents: Option<&Vec<i32>>) -> bool {
+        let mut epochs = self.epochs.lock().unwrap();
+        let ready = epochs.get(&job_id) == Some(&Epoch::Pending)
+            && parents
+                .into_iter()
+                .flatten()
+                .all(|p| epochs.get(p) == Some(&Epoch::Completed));
+        if ready {
+            epochs.insert(job_id, Epoch::Queued);
+        }
+        ready
+    }
+
+    pub fn is_skipped(&self, job_id: i32) -> bool {
+        self.epochs.lock().unwrap().get(&job_id) == Some(&Epoch::Skipped)
+    }
+
+    /// Drop one in-flight reference; the last one to leave closes the group run.
+    async fn release(&self, pool: &PgPool) {
+        if self.in_flight.fetch_sub(1, Ordering::SeqCst) != 1 {
+            return;
+        }
+        self.active_runs.0.lock().unwrap().remove(&self.run_id);
+        // Jobs still pending never became eligible (e.g. `timings` not reached yet)
+        let (failed, never_started) = {
+            let mut epochs = self.epochs.lock().unwrap();
+            let never_started: Vec<i32> = epochs
+                .iter_mut()
+                .filter(|(_, e)| **e == Epoch::Pending)
+                .map(|(&id, e)| {
+                    *e = Epoch::Skipped;
+                    id
+                })
+                .collect();
+            (epochs.values().any(|e| *e == Epoch::Failed), never_started)
+        };
+        for job_id in never_started {
+            set_job_status(pool, job_id, self.group_id, "skipped").await;
+        }
+        let status = if self.cancel.is_cancelled() {
+            "cancelled"
+        } else if failed {
+            "failed"
+        } else {
+            "completed"
+        };
+        finish_group_run(pool, self.run_id, status).await;
+        self.metrics
+            .group_runs
+            .with_label_values(&[&self.group_id.to_string(), status])
+            .inc();
+        self.webhooks.notify(WebhookEvent {
+            event: "run_completed",
+            group_id: self.group_id,
+            run_id: self.run_id,
+            job_id: None,
+            job_name: None,
+            attempt: None,
+            will_retry: false,
+            status: status.to_string(),
+            duration_seconds: self.started.elapsed().as_secs_f64(),
+            log_excerpt: None,
+            timestamp: Utc::now(),
+        });
+    }
 }
 
 pub struct JobSpec {
@@ -178,24 +743,32 @@ pub struct JobSpec {
 }
 
 pub struct JobStatusReport {
+    pub run_id: Uuid,
     pub running: Vec<i32>,
     pub completed: Vec<i32>,
     pub pending: Vec<i32>,
+    pub skipped: Vec<i32>,
 }
 
 pub async fn run_group_jobs_with_command(
     order: Vec<i32>,
     jobs: HashMap<i32, CronJob>,
     dependencies: HashMap<i32, Vec<i32>>,
-    group_id: &str,
     epoch_state: EpochState,
     pool: PgPool,
 ) -> Result<JobStatusReport, String> {
-    // Set all jobs to Pending in memory
+    // Build dependents map
+    let dependents = std::sync::Arc::new(build_dependents_map(&dependencies));
+
+    // Set every job this run may start to Pending in memory, keeping any that were already skipped
     {
         let mut epochs = epoch_state.epochs.lock().unwrap();
-        for job_id in &order {
-            epochs.insert(*job_id, Epoch::Pending);
+        let mut queue: Vec<i32> = order.clone();
+        while let Some(job_id) = queue.pop() {
+            if let std::collections::hash_map::Entry::Vacant(e) = epochs.entry(job_id) {
+                e.insert(Epoch::Pending);
+                queue.extend(dependents.get(&job_id).into_iter().flatten());
+            }
         }
     }
 
@@ -203,47 +776,202 @@ pub async fn run_group_jobs_with_command(
     let mut running = Vec::new();
     let mut pending = Vec::new();
     let mut completed = Vec::new();
+    let mut skipped = Vec::new();
 
-    // Build dependents map
-    let dependents = std::sync::Arc::new(build_dependents_map(&dependencies));
     let jobs_arc = std::sync::Arc::new(jobs);
     let dependencies_arc = std::sync::Arc::new(dependencies);
 
+    // Hold a reference while launching so the run cannot close before every root is spawned
+    epoch_state.in_flight.fetch_add(1, Ordering::SeqCst);
     for &job_id in &order {
         let job = jobs_arc.get(&job_id).unwrap();
+        if epoch_state.is_skipped(job_id) {
+            skipped.push(job_id);
+            continue;
+        }
         if now < job.timings {
             pending.push(job_id);
             continue;
         }
-        if let Some(parents) = dependencies_arc.get(&job_id) {
-            let epochs = epoch_state.epochs.lock().unwrap();
-            if !parents.iter().all(|p| epochs.get(p) == Some(&Epoch::Completed)) {
-                pending.push(job_id);
-                continue;
-            }
+        if !epoch_state.claim(job_id, dependencies_arc.get(&job_id)) {
+            pending.push(job_id);
+            continue;
         }
         spawn_job_and_dependents(job_id, jobs_arc.clone(), dependencies_arc.clone(), dependents.clone(), epoch_state.clone(), pool.clone());
         running.push(job_id);
     }
+    epoch_state.release(&pool).await;
 
     // All jobs not started are pending
     for &job_id in &order {
         let epochs = epoch_state.epochs.lock().unwrap();
         if let Some(Epoch::Completed) = epochs.get(&job_id) {
             completed.push(job_id);
-        } else if !running.contains(&job_id) && !pending.contains(&job_id) {
+        } else if !running.contains(&job_id) && !pending.contains(&job_id) && !skipped.contains(&job_id) {
             pending.push(job_id);
         }
     }
 
     Ok(JobStatusReport {
+        run_id: epoch_state.run_id,
         running,
         completed,
         pending,
+        skipped,
     })
 }
 
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn retrying_job(retry_delay_seconds: i32, backoff_multiplier: f64) -> CronJob {
+        CronJob {
+            cron_job_id: 1,
+            group_id: Uuid::nil(),
+            cron_job_name: "job".to_string(),
+            timings: Utc::now(),
+            schedule: None,
+            children: None,
+            s3_link: None,
+            dockerfile_path: None,
+            max_retries: 5,
+            retry_delay_seconds,
+            backoff_multiplier,
+            timeout_seconds: None,
+            paused: false,
+        }
+    }
+
+    #[test]
+    fn retry_delay_grows_by_the_backoff_multiplier() {
+        let job = retrying_job(10, 2.0);
+        assert_eq!(retry_delay(&job, 1), Duration::from_secs(10));
+        assert_eq!(retry_delay(&job, 2), Duration::from_secs(20));
+        assert_eq!(retry_delay(&job, 4), Duration::from_secs(80));
+    }
+
+    #[test]
+    fn retry_delay_is_constant_without_backoff() {
+        let job = retrying_job(7, 1.0);
+        assert_eq!(retry_delay(&job, 1), retry_delay(&job, 6));
+        assert_eq!(retry_delay(&retrying_job(0, 2.0), 3), Duration::ZERO);
+    }
+
+    #[test]
+    fn retry_delay_is_capped() {
+        assert_eq!(retry_delay(&retrying_job(60, 10.0), 5), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(&retrying_job(i32::MAX, 2.0), 1), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(&retrying_job(1, f64::MAX), 3), MAX_RETRY_DELAY);
+    }
+
+    /// A fresh directory with an empty `context` subdirectory, removed when dropped.
+    struct Scratch(std::path::PathBuf);
+
+    impl Scratch {
+        fn new() -> Self {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let n = NEXT.fetch_add(1, Ordering::SeqCst);
+            let dir = std::env::temp_dir().join(format!("unpack-test-{}-{}", std::process::id(), n));
+            fs::create_dir_all(dir.join("context")).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// A tar archive of `(path, contents)` files. Paths are written into the
+    /// header as-is, since `tar::Builder` refuses to create `..` entries.
+    fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, contents) in files {
+            let mut header = tar::Header::new_gnu();
+            header.as_old_mut().name[..path.len()].copy_from_slice(path.as_bytes());
+            header.set_size(contents.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append(&header, *contents).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn unpacks_files_inside_the_context() {
+        let dir = Scratch::new();
+        let bytes = archive(&[("Dockerfile", b"FROM scratch\n"), ("src/main.sh", b"echo hi\n")]);
+        unpack_archive(bytes.as_slice(), &dir.0).unwrap();
+        assert_eq!(fs::read(dir.0.join("Dockerfile")).unwrap(), b"FROM scratch\n");
+        assert_eq!(fs::read(dir.0.join("src/main.sh")).unwrap(), b"echo hi\n");
+    }
+
+    #[test]
+    fn rejects_entries_escaping_the_context() {
+        let dir = Scratch::new();
+        let bytes = archive(&[("Dockerfile", b"FROM scratch\n"), ("../escaped", b"oops")]);
+        let err = unpack_archive(bytes.as_slice(), &dir.0.join("context")).unwrap_err();
+        assert!(err.contains("outside the archive root"), "{}", err);
+        assert!(!dir.0.join("escaped").exists());
+    }
+
+    #[test]
+    fn rejects_links() {
+        let dir = Scratch::new();
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, "Dockerfile", "/etc/passwd").unwrap();
+        let bytes = builder.into_inner().unwrap();
+        let err = unpack_archive(bytes.as_slice(), &dir.0).unwrap_err();
+        assert!(err.contains("may not contain links"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_oversized_archives() {
+        let dir = Scratch::new();
+        // The header alone claims more than the cap; no data follows it
+        let mut header = tar::Header::new_gnu();
+        header.set_path("huge").unwrap();
+        header.set_size(MAX_BUILD_CONTEXT_BYTES + 1);
+        header.set_mode(0o644);
+        header.set_cksum();
+        let err = unpack_archive(header.as_bytes().as_slice(), &dir.0).unwrap_err();
+        assert!(err.contains("unpacks to more than"), "{}", err);
+        assert!(!dir.0.join("huge").exists());
+
+        let mut builder = tar::Builder::new(Vec::new());
+        for i in 0..=MAX_BUILD_CONTEXT_ENTRIES {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Fifo);
+            header.set_path(format!("fifo{}", i)).unwrap();
+            header.set_size(0);
+            header.set_cksum();
+            builder.append(&header, std::io::empty()).unwrap();
+        }
+        let bytes = builder.into_inner().unwrap();
+        let err = unpack_archive(bytes.as_slice(), &dir.0).unwrap_err();
+        assert!(err.contains("more than 10000 entries"), "{}", err);
+    }
+
+    #[test]
+    fn build_context_needs_the_selected_dockerfile() {
+        let dir = Scratch::new();
+        let bytes = archive(&[("docker/Dockerfile.job", b"FROM scratch\n")]);
+        assert!(unpack_build_context(&bytes, &dir.0, "docker/Dockerfile.job").is_ok());
+        let err = unpack_build_context(&bytes, &dir.0.join("other"), "Dockerfile").unwrap_err();
+        assert!(err.contains("no Dockerfile at 'Dockerfile'"), "{}", err);
+
+        // Anything that is not an archive is the Dockerfile itself
+        unpack_build_context(b"FROM scratch\n", &dir.0.join("plain"), "Dockerfile").unwrap();
+        assert!(dir.0.join("plain/Dockerfile").is_file());
+    }
+}
+
 // 5c5ccd2b-9e9f-4b69-9d71-c3ad2045e17a
 // 7c1a091e-6cdf-4b18-b4d9-2133f2c46599
 // 17676425-c4ea-4d90-a91f-b49a14fcf7ee
\ No newline at end of file
@@ -0,0 +1,126 @@
+use crate::scheduler::cron::{CronJob, execute_group};
+use crate::state::AppState;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How often the scheduler loop wakes up to look for due jobs.
+const TICK: Duration = Duration::from_secs(1);
+
+/// Parse a cron expression in either standard 5-field syntax
+/// (`min hour dom month dow`) or 6-field syntax with a leading seconds field.
+pub fn parse_schedule(expr: &str) -> Result<Schedule, String> {
+    let fields = expr.split_whitespace().count();
+    let normalized = match fields {
+        5 => format!("0 {}", expr.trim()),
+        6 => expr.trim().to_string(),
+        _ => {
+            return Err(format!(
+                "invalid cron expression '{}': expected 5 or 6 fields, got {}",
+                expr, fields
+            ));
+        }
+    };
+    Schedule::from_str(&normalized).map_err(|e| format!("invalid cron expression '{}': {}", expr, e))
+}
+
+/// Returns true if `schedule` has a fire time in the window `(since, until]`.
+fn fires_between(schedule: &Schedule, since: DateTime<Utc>, until: DateTime<Utc>) -> bool {
+    schedule.after(&since).next().is_some_and(|next| next <= until)
+}
+
+/// Long-running task that triggers jobs whose cron `schedule` fires.
+///
+/// Every tick the loop loads all scheduled jobs, collects the ones with a fire
+/// time since the previous tick and executes them per group. Firings missed
+/// while the server was down are not replayed.
+pub async fn run_scheduler(state: AppState) {
+    let mut interval = tokio::time::interval(TICK);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut last_tick = Utc::now();
+
+    loop {
+        interval.tick().await;
+        let now = Utc::now();
+
+        let jobs = match sqlx::query_as::<_, CronJob>(
+            r#"
+            SELECT j.* FROM cron_jobs j
+            JOIN job_groups g ON g.group_id = j.group_id
+            WHERE j.schedule IS NOT NULL AND NOT j.paused AND NOT g.paused
+            "#,
+        )
+        .fetch_all(&state.pool)
+        .await
+        {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::error!(error = %e, "scheduler failed to load jobs");
+                continue;
+            }
+        };
+
+        // group_id -> jobs due this tick
+        let mut due: HashMap<Uuid, Vec<i32>> = HashMap::new();
+        for job in &jobs {
+            let Some(expr) = &job.schedule else { continue };
+            match parse_schedule(expr) {
+                Ok(schedule) if fires_between(&schedule, last_tick, now) => {
+                    due.entry(job.group_id).or_default().push(job.cron_job_id);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!(job_id = job.cron_job_id, error = %e, "skipping job with invalid schedule"),
+            }
+        }
+        last_tick = now;
+
+        for (group_id, job_ids) in due {
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err((_, e)) = execute_group(&state, group_id, Some(job_ids), false).await {
+                    tracing::error!(%group_id, error = %e, "scheduled run failed");
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn five_field_expressions_fire_on_the_minute() {
+        let schedule = parse_schedule("*/5 * * * *").unwrap();
+        let since = DateTime::parse_from_rfc3339("2024-01-01T00:01:30Z").unwrap().with_timezone(&Utc);
+        let next = schedule.after(&since).next().unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-01-01T00:05:00+00:00");
+    }
+
+    #[test]
+    fn six_field_expressions_keep_their_seconds() {
+        let schedule = parse_schedule(" 30 0 12 * * * ").unwrap();
+        let since = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let next = schedule.after(&since).next().unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-01-01T12:00:30+00:00");
+    }
+
+    #[test]
+    fn rejects_wrong_field_counts_and_bad_fields() {
+        let err = parse_schedule("* * * *").unwrap_err();
+        assert!(err.contains("expected 5 or 6 fields, got 4"), "{}", err);
+        assert!(parse_schedule("* * * * * * * *").is_err());
+        assert!(parse_schedule("61 * * * *").is_err());
+    }
+
+    #[test]
+    fn fires_between_excludes_the_start_of_the_window() {
+        let schedule = parse_schedule("0 * * * *").unwrap();
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        assert!(fires_between(&schedule, at("2024-01-01T00:59:59Z"), at("2024-01-01T01:00:00Z")));
+        assert!(!fires_between(&schedule, at("2024-01-01T01:00:00Z"), at("2024-01-01T01:00:01Z")));
+    }
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use uuid::Uuid;
+
+/// Number of jobs allowed to run at once when `executor.max_running_jobs` is not set.
+pub const DEFAULT_MAX_RUNNING_JOBS: usize = 8;
+
+/// Limits on how many jobs may run at once, across all groups and per group.
+///
+/// A group's limit is its `max_parallelism` and is shared by all of its
+/// concurrent runs. Waiters are served in the order they asked for a slot.
+#[derive(Clone)]
+pub struct JobSlots {
+    global: Arc<Semaphore>,
+    groups: Arc<Mutex<HashMap<Uuid, GroupSlots>>>,
+}
+
+/// Slots of one group, sized by the `max_parallelism` they were created with.
+struct GroupSlots {
+    limit: usize,
+    slots: Arc<Semaphore>,
+}
+
+impl GroupSlots {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            slots: Arc::new(Semaphore::new(limit)),
+        }
+    }
+}
+
+/// A held slot; dropping it lets the next queued job start.
+pub struct JobSlot {
+    _group: Option<OwnedSemaphorePermit>,
+    _global: OwnedSemaphorePermit,
+}
+
+impl JobSlots {
+    pub fn new(max_running: usize) -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(max_running)),
+            groups: Arc::default(),
+        }
+    }
+
+    /// Slots for `group_id`, replaced when its `max_parallelism` has changed.
+    fn group(&self, group_id: Uuid, limit: Option<usize>) -> Option<Arc<Semaphore>> {
+        let mut groups = self.groups.lock().unwrap();
+        let Some(limit) = limit else {
+            groups.remove(&group_id);
+            return None;
+        };
+        let group = groups.entry(group_id).or_insert_with(|| GroupSlots::new(limit));
+        if group.limit != limit {
+            *group = GroupSlots::new(limit);
+        }
+        Some(group.slots.clone())
+    }
+
+    /// Take a slot without waiting, if both the group and the global limit allow it.
+    pub fn try_acquire(&self, group_id: Uuid, max_parallelism: Option<usize>) -> Option<JobSlot> {
+        let group = match self.group(group_id, max_parallelism) {
+            Some(slots) => Some(slots.try_acquire_owned().ok()?),
+            None => None,
+        };
+        let global = self.global.clone().try_acquire_owned().ok()?;
+        Some(JobSlot { _group: group, _global: global })
+    }
+
+    /// Wait for a slot. The group slot is taken first so a job held back by its
+    /// own group does not occupy a global slot meanwhile.
+    pub async fn acquire(&self, group_id: Uuid, max_parallelism: Option<usize>) -> JobSlot {
+        let group = match self.group(group_id, max_parallelism) {
+            Some(slots) => Some(slots.acquire_owned().await.expect("job slots are never closed")),
+            None => None,
+        };
+        let global = self
+            .global
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("job slots are never closed");
+        JobSlot { _group: group, _global: global }
+    }
+}
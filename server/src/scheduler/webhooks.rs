@@ -0,0 +1,278 @@
+use axum::{
+    extract::{Json, Path, Query, State},
+    http::StatusCode,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{FromRow, PgPool};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Attempts made to deliver one event before it is recorded as failed.
+const MAX_DELIVERY_ATTEMPTS: i32 = 5;
+
+/// Delay before the first redelivery, doubled after every further failure.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Time allowed for a webhook endpoint to respond.
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Most bytes of a job's log included in a job event.
+pub const LOG_EXCERPT_BYTES: u64 = 4096;
+
+/// Sends group events to the group's webhooks and records each delivery.
+#[derive(Clone)]
+pub struct Webhooks {
+    pool: PgPool,
+    client: reqwest::Client,
+}
+
+/// JSON body POSTed to a webhook.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookEvent {
+    pub event: &'static str, // 'job_failed', 'job_timed_out', 'run_completed'
+    pub group_id: Uuid,
+    pub run_id: Uuid,
+    pub job_id: Option<i32>,          // job events only
+    pub job_name: Option<String>,     // job events only
+    pub attempt: Option<i32>,         // job events only
+    pub will_retry: bool,             // whether the job has attempts left
+    pub status: String,               // attempt status, or the final status of the run
+    pub duration_seconds: f64,        // of the attempt, or of the whole run
+    pub log_excerpt: Option<String>,  // end of the attempt's log
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Webhooks {
+    pub fn new(pool: PgPool) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .build()
+            .expect("webhook client has a valid configuration");
+        Self { pool, client }
+    }
+
+    /// Send `event` to every webhook of its group in the background.
+    pub fn notify(&self, event: WebhookEvent) {
+        let webhooks = self.clone();
+        tokio::spawn(async move {
+            let hooks = match sqlx::query!(
+                "SELECT webhook_id, url FROM group_webhooks WHERE group_id = $1",
+                event.group_id
+            )
+            .fetch_all(&webhooks.pool)
+            .await
+            {
+                Ok(hooks) => hooks,
+                Err(e) => {
+                    tracing::error!(error = %e, "failed to load webhooks");
+                    return;
+                }
+            };
+            let payload = serde_json::to_value(&event).unwrap_or_default();
+            for hook in hooks {
+                let delivery_id = match sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO webhook_deliveries (webhook_id, run_id, event, payload, status)
+                    VALUES ($1, $2, $3, $4, 'pending')
+                    RETURNING delivery_id
+                    "#,
+                    hook.webhook_id,
+                    event.run_id,
+                    event.event,
+                    payload
+                )
+                .fetch_one(&webhooks.pool)
+                .await
+                {
+                    Ok(id) => id,
+                    Err(e) => {
+                        tracing::error!(webhook_id = hook.webhook_id, error = %e, "failed to record webhook delivery");
+                        continue;
+                    }
+                };
+                tokio::spawn(webhooks.clone().deliver(delivery_id, hook.url, payload.clone()));
+            }
+        });
+    }
+
+    /// POST `payload` to `url`, retrying with backoff, and record every attempt.
+    async fn deliver(self, delivery_id: i32, url: String, payload: serde_json::Value) {
+        let mut delay = FIRST_RETRY_DELAY;
+        for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+            let (response_status, error) = match self.client.post(&url).json(&payload).send().await {
+                Ok(response) if response.status().is_success() => (Some(response.status().as_u16() as i32), None),
+                Ok(response) => (
+                    Some(response.status().as_u16() as i32),
+                    Some(format!("endpoint returned {}", response.status())),
+                ),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            let status = match &error {
+                None => "delivered",
+                Some(_) if attempt == MAX_DELIVERY_ATTEMPTS => "failed",
+                Some(_) => "pending",
+            };
+            let _ = sqlx::query!(
+                r#"
+                UPDATE webhook_deliveries
+                SET status = $2, attempts = $3, response_status = $4, last_error = $5, updated_at = NOW()
+                WHERE delivery_id = $1
+                "#,
+                delivery_id,
+                status,
+                attempt,
+                response_status,
+                error
+            )
+            .execute(&self.pool)
+            .await;
+
+            let Some(error) = error else { return };
+            if status == "failed" {
+                tracing::warn!(delivery_id, %url, %error, "webhook delivery failed");
+                return;
+            }
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct AddWebhookRequest {
+    url: String,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Webhook {
+    pub webhook_id: i32,
+    pub group_id: Uuid,
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Reject anything but an absolute http(s) URL.
+fn validate_url(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid url '{}': {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("invalid url '{}': expected http or https", url));
+    }
+    Ok(())
+}
+
+/// Handler to register a webhook for a group.
+pub async fn add_webhook(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+    Json(payload): Json<AddWebhookRequest>,
+) -> Result<(StatusCode, Json<Webhook>), (StatusCode, String)> {
+    validate_url(&payload.url).map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    let exists = sqlx::query_scalar!("SELECT EXISTS(SELECT 1 FROM job_groups WHERE group_id = $1)", group_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if exists != Some(true) {
+        return Err((StatusCode::NOT_FOUND, format!("group {} not found", group_id)));
+    }
+
+    let webhook = sqlx::query_as!(
+        Webhook,
+        "INSERT INTO group_webhooks (group_id, url) VALUES ($1, $2) RETURNING webhook_id, group_id, url, created_at",
+        group_id,
+        payload.url
+    )
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok((StatusCode::CREATED, Json(webhook)))
+}
+
+/// Handler to list the webhooks of a group.
+pub async fn get_webhooks(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+) -> Result<Json<Vec<Webhook>>, (StatusCode, String)> {
+    let webhooks = sqlx::query_as!(
+        Webhook,
+        "SELECT webhook_id, group_id, url, created_at FROM group_webhooks WHERE group_id = $1 ORDER BY webhook_id",
+        group_id
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(webhooks))
+}
+
+/// Handler to remove a webhook, along with its delivery history.
+pub async fn delete_webhook(
+    State(pool): State<PgPool>,
+    Path((group_id, webhook_id)): Path<(Uuid, i32)>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let result = sqlx::query!(
+        "DELETE FROM group_webhooks WHERE group_id = $1 AND webhook_id = $2",
+        group_id,
+        webhook_id
+    )
+    .execute(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, format!("webhook {} not found", webhook_id)));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Query parameters for listing deliveries.
+#[derive(Debug, Deserialize)]
+pub struct DeliveryQuery {
+    pub status: Option<String>, // e.g. 'failed'
+    pub limit: Option<i64>,     // defaults to 50, at most 1000
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct WebhookDelivery {
+    pub delivery_id: i32,
+    pub webhook_id: i32,
+    pub url: String,
+    pub run_id: Uuid,
+    pub event: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Handler to list the latest webhook deliveries of a group, newest first.
+pub async fn get_webhook_deliveries(
+    State(pool): State<PgPool>,
+    Path(group_id): Path<Uuid>,
+    Query(query): Query<DeliveryQuery>,
+) -> Result<Json<Vec<WebhookDelivery>>, (StatusCode, String)> {
+    let limit = query.limit.unwrap_or(50);
+    if !(1..=1000).contains(&limit) {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, "limit must be between 1 and 1000".to_string()));
+    }
+    let deliveries = sqlx::query_as!(
+        WebhookDelivery,
+        r#"
+        SELECT d.delivery_id, d.webhook_id, w.url, d.run_id, d.event, d.payload, d.status,
+               d.attempts, d.response_status, d.last_error, d.created_at, d.updated_at
+        FROM webhook_deliveries d
+        JOIN group_webhooks w ON w.webhook_id = d.webhook_id
+        WHERE w.group_id = $1 AND ($2::text IS NULL OR d.status = $2)
+        ORDER BY d.delivery_id DESC
+        LIMIT $3
+        "#,
+        group_id,
+        query.status,
+        limit
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(deliveries))
+}
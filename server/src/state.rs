@@ -0,0 +1,45 @@
+use crate::config::Config;
+use crate::metrics::Metrics;
+use crate::scheduler::events::StatusEvents;
+use crate::scheduler::s3::ActiveRuns;
+use crate::scheduler::slots::JobSlots;
+use crate::scheduler::webhooks::Webhooks;
+use axum::extract::FromRef;
+use sqlx::PgPool;
+use std::sync::Arc;
+
+/// Shared application state handed to every handler and the scheduler loop.
+///
+/// Handlers that only need the database can keep extracting `State<PgPool>`.
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: PgPool,
+    pub config: Arc<Config>,
+    pub docker: shiplift::Docker,
+    pub runs: ActiveRuns, // group runs that are still executing
+    pub slots: JobSlots,  // limits on concurrently running jobs
+    pub metrics: Metrics,
+    pub webhooks: Webhooks, // notifies groups' webhooks of failures and finished runs
+    pub events: StatusEvents, // status changes relayed to live event streams
+}
+
+impl AppState {
+    pub fn new(pool: PgPool, config: Config) -> Self {
+        Self {
+            webhooks: Webhooks::new(pool.clone()),
+            pool,
+            docker: config.docker(),
+            runs: ActiveRuns::default(),
+            slots: JobSlots::new(config.executor.max_running_jobs),
+            metrics: Metrics::new(),
+            events: StatusEvents::new(),
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl FromRef<AppState> for PgPool {
+    fn from_ref(state: &AppState) -> PgPool {
+        state.pool.clone()
+    }
+}